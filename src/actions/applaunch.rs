@@ -1,35 +1,63 @@
+use crate::loader::desktop_entry::{expand_exec, split_exec, FieldCodes};
 use crate::loader::util::{SherlockError, SherlockErrorType};
 use crate::CONFIG;
 use std::{
     os::unix::process::CommandExt,
-    process::{exit, Command, Stdio},
+    process::{Command, Stdio},
 };
 
-pub fn applaunch(exec: &str) -> Result<(), SherlockError> {
+use super::commandlaunch::terminal_args;
+
+/// How an app is launched, besides its exec
+#[derive(Debug, Default)]
+pub struct LaunchOptions<'a> {
+    pub cwd: Option<&'a str>,
+    pub env: Vec<(&'a str, &'a str)>,
+    /// Replaces the `launch_prefix` of the config. Empty to launch without any
+    /// prefix.
+    pub launch_prefix: Option<&'a str>,
+    /// Run the app in the terminal of `default_apps`
+    pub terminal: bool,
+    pub codes: FieldCodes<'a>,
+}
+
+pub fn applaunch(exec: &str, options: &LaunchOptions) -> Result<(), SherlockError> {
     let config = CONFIG.get().ok_or(SherlockError {
         error: SherlockErrorType::ConfigError(None),
         traceback: format!(""),
     })?;
 
-    let parts: Vec<String> = match &config.behavior.launch_prefix {
-        Some(prefix) => String::from(prefix) + " " + exec,
-        None => String::from(exec),
+    let quote_error = || SherlockError {
+        error: SherlockErrorType::CommandExecutionError(exec.to_string()),
+        traceback: String::from("The command contains an unterminated quote"),
+    };
+    let prefix = options
+        .launch_prefix
+        .or(config.behavior.launch_prefix.as_deref());
+    let mut parts: Vec<String> = match prefix {
+        Some(prefix) => split_exec(prefix).ok_or_else(quote_error)?,
+        None => Vec::new(),
+    };
+    let args = expand_exec(exec, &options.codes).ok_or_else(quote_error)?;
+    match options.terminal {
+        true => parts.extend(terminal_args(args, false)?),
+        false => parts.extend(args),
     }
-    .split_whitespace()
-    .map(String::from)
-    .collect();
 
     if parts.is_empty() {
-        eprintln!("Error: Command is empty");
-        exit(1);
+        return Err(SherlockError {
+            error: SherlockErrorType::CommandExecutionError(exec.to_string()),
+            traceback: String::from("The command is empty"),
+        });
     }
 
     let mut command = Command::new(&parts[0]);
-    for arg in &parts[1..] {
-        if !arg.starts_with("%") {
-            command.arg(arg);
-        }
+    command.args(&parts[1..]);
+
+    if let Some(dir) = options.cwd {
+        command.current_dir(dir);
     }
+    command.envs(options.env.iter().copied());
 
     #[cfg(target_family = "unix")]
     unsafe {
@@ -43,7 +71,9 @@ pub fn applaunch(exec: &str) -> Result<(), SherlockError> {
             });
     }
 
-    // TODO make error handling so that error tile will show up
-    let _output = command.spawn();
+    command.spawn().map_err(|e| SherlockError {
+        error: SherlockErrorType::CommandExecutionError(parts[0].clone()),
+        traceback: e.to_string(),
+    })?;
     Ok(())
 }
@@ -1,30 +1,280 @@
 use std::{
+    collections::HashMap,
+    env,
     os::unix::process::CommandExt,
+    path::{Path, PathBuf},
     process::{Command, Stdio},
 };
 
-use crate::loader::util::{SherlockError, SherlockErrorType};
+use crate::loader::desktop_entry::split_exec;
+use crate::loader::util::{expand_path, home_dir, SherlockError, SherlockErrorType};
+use crate::CONFIG;
 
-pub fn command_launch(exec: &str, keyword: &str) -> Result<(), SherlockError> {
-    let exec = exec.replace("{keyword}", &keyword);
-    let commands: Vec<&str> = exec.split("&").collect();
+/// How a command of a chain is joined to the next one
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Separator {
+    /// `&`: start the command and the next one right away
+    Background,
+    /// `;`: start the next command once this one exited
+    Sequence,
+    /// `&&`: start the next command if this one succeeded
+    And,
+    /// `||`: start the next command if this one failed
+    Or,
+    /// The last command of the chain
+    End,
+}
+
+/// One command of a chain, with its leading `NAME=value` assignments
+#[derive(Debug, PartialEq)]
+pub struct Step {
+    pub env: Vec<(String, String)>,
+    pub args: Vec<String>,
+    pub then: Separator,
+}
 
-    for command in commands {
-        if !command.is_empty() {
-            let mut parts = command.split_whitespace();
-            let execute = parts.next().expect("No command found");
-            let args: Vec<&str> = parts.collect();
-            asynchronous_execution(execute, args)?;
+/// Values for placeholders like `{keyword}` in an exec
+pub type Placeholders<'a> = [(&'a str, &'a str)];
+
+/// How the exec of a `command` entry is run
+#[derive(Debug, Default)]
+pub struct CommandOptions<'a> {
+    /// Pass the exec to `$SHELL -c` as is instead of splitting it
+    pub shell: bool,
+    pub cwd: Option<&'a str>,
+    pub env: Vec<(&'a str, &'a str)>,
+    /// Run the command in the terminal of `default_apps`
+    pub terminal: bool,
+    /// Keep the terminal open after the command exited
+    pub hold: bool,
+}
+impl<'a> CommandOptions<'a> {
+    pub fn from_attrs(attrs: &'a HashMap<String, String>) -> Self {
+        let flag = |key: &str| attrs.get(key).is_some_and(|v| v == "true");
+        CommandOptions {
+            shell: flag("shell"),
+            cwd: attrs.get("cwd").map(|s| s.as_str()),
+            env: attrs
+                .iter()
+                .filter_map(|(k, v)| Some((k.strip_prefix("env:")?, v.as_str())))
+                .collect(),
+            terminal: flag("terminal"),
+            hold: flag("hold"),
         }
     }
+    /// The environment of the entry, followed by the variables set in the exec
+    fn env_with(&self, env: Vec<(String, String)>) -> Vec<(String, String)> {
+        self.env
+            .iter()
+            .map(|(k, v)| (k.to_string(), v.to_string()))
+            .chain(env)
+            .collect()
+    }
+}
+
+/// Runs the exec of a `command` entry. Placeholders like `{keyword}` are
+/// replaced after parsing, so whatever was typed ends up in the arguments
+/// verbatim.
+///
+/// With `shell` set, the exec is passed to `$SHELL -c` as is, and the values
+/// of the placeholders are inserted quoted.
+pub fn command_launch(
+    exec: &str,
+    placeholders: &Placeholders,
+    options: &CommandOptions,
+) -> Result<(), SherlockError> {
+    if options.terminal {
+        let step = command_step(exec, placeholders, options.shell)?;
+        let args = terminal_args(step.args, options.hold)?;
+        return spawn_detached(&args, &options.env_with(step.env), options.cwd);
+    }
+    if options.shell {
+        let args = shell_args(exec, placeholders);
+        return spawn_detached(&args, &options.env_with(Vec::new()), options.cwd);
+    }
+    let steps = parse_with_placeholders(exec, placeholders)?;
+    let waits = steps
+        .iter()
+        .any(|s| matches!(s.then, Separator::Sequence | Separator::And | Separator::Or));
+    if waits {
+        // Waiting for a command must neither block Sherlock nor end with it,
+        // so the chain is handed to a detached shell with every word quoted
+        let script = to_script(&steps);
+        let args = [String::from("/bin/sh"), String::from("-c"), script];
+        return spawn_detached(&args, &options.env_with(Vec::new()), options.cwd);
+    }
+    for step in steps {
+        spawn_detached(&step.args, &options.env_with(step.env), options.cwd)?;
+    }
     Ok(())
 }
 
-fn asynchronous_execution(execute: &str, args: Vec<&str>) -> Result<(), SherlockError> {
+/// Turns the whole exec into a single step, so it can run as one process
+fn command_step(
+    exec: &str,
+    placeholders: &Placeholders,
+    shell: bool,
+) -> Result<Step, SherlockError> {
+    let mut steps = match shell {
+        true => Vec::new(),
+        false => parse_with_placeholders(exec, placeholders)?,
+    };
+    if steps.len() == 1 {
+        return Ok(steps.remove(0));
+    }
+    let args = match shell {
+        true => shell_args(exec, placeholders),
+        false => vec![
+            String::from("/bin/sh"),
+            String::from("-c"),
+            to_script(&steps),
+        ],
+    };
+    Ok(Step {
+        env: Vec::new(),
+        args,
+        then: Separator::End,
+    })
+}
+
+/// Runs `args` in the terminal of `default_apps`
+pub(crate) fn terminal_args(args: Vec<String>, hold: bool) -> Result<Vec<String>, SherlockError> {
+    let terminal = CONFIG
+        .get()
+        .map_or("", |c| c.default_apps.terminal.as_str());
+    in_terminal(terminal, args, hold).ok_or_else(|| SherlockError {
+        error: SherlockErrorType::ConfigError(Some(String::from("Failed to get terminal"))),
+        traceback: String::from(
+            "The command should run in a terminal, but no terminal app is set in the config.",
+        ),
+    })
+}
+
+/// Prefixes `args` with the words of `terminal`. With `hold`, the terminal
+/// waits for Return after the command exited.
+fn in_terminal(terminal: &str, args: Vec<String>, hold: bool) -> Option<Vec<String>> {
+    let mut wrapped = split_exec(terminal).filter(|words| !words.is_empty())?;
+    if hold {
+        let script =
+            r#""$@"; printf '\n[Exited with code %s, press Return to close]' "$?"; read -r _"#;
+        wrapped.extend(["/bin/sh", "-c", script, "sh"].map(String::from));
+    }
+    wrapped.extend(args);
+    Some(wrapped)
+}
+
+/// The working directory of a command, which may start with `~`
+fn working_dir(cwd: &str) -> PathBuf {
+    match home_dir() {
+        Ok(home) => expand_path(Path::new(cwd), &home),
+        Err(_) => PathBuf::from(cwd),
+    }
+}
+
+/// Parses the exec and replaces the placeholders in every word
+fn parse_with_placeholders(
+    exec: &str,
+    placeholders: &Placeholders,
+) -> Result<Vec<Step>, SherlockError> {
+    let execution_error = |traceback: String| SherlockError {
+        error: SherlockErrorType::CommandExecutionError(exec.to_string()),
+        traceback,
+    };
+    let mut steps = parse_command(exec).map_err(execution_error)?;
+    if steps.is_empty() {
+        return Err(execution_error(String::from("The command is empty")));
+    }
+    let fill = |text: &mut String| {
+        for (placeholder, value) in placeholders {
+            *text = text.replace(placeholder, value);
+        }
+    };
+    for step in steps.iter_mut() {
+        step.args.iter_mut().for_each(fill);
+        step.env.iter_mut().for_each(|(_, value)| fill(value));
+    }
+    Ok(steps)
+}
+
+/// Arguments to run the exec through `$SHELL -c`
+fn shell_args(exec: &str, placeholders: &Placeholders) -> Vec<String> {
+    let shell = env::var("SHELL")
+        .ok()
+        .filter(|s| !s.is_empty())
+        .unwrap_or(String::from("/bin/sh"));
+    let mut script = exec.to_string();
+    for (placeholder, value) in placeholders {
+        script = script.replace(placeholder, &shell_quote(value));
+    }
+    vec![shell, String::from("-c"), script]
+}
+
+/// What a command that was waited for left behind
+#[derive(Clone, Debug, Default)]
+pub struct CommandOutput {
+    /// Standard output without its trailing newline
+    pub stdout: String,
+    /// `-1` if the command was ended by a signal
+    pub exit_code: i32,
+}
+impl CommandOutput {
+    pub fn success(&self) -> bool {
+        self.exit_code == 0
+    }
+}
+
+/// Runs the exec like [`command_launch`], but waits for it to exit and
+/// captures its output. Blocks, so it must not be called on the main thread.
+/// Commands in a terminal print to the terminal instead.
+pub fn command_output(
+    exec: &str,
+    placeholders: &Placeholders,
+    options: &CommandOptions,
+) -> Result<CommandOutput, SherlockError> {
+    let step = command_step(exec, placeholders, options.shell)?;
+    let args = match options.terminal {
+        true => terminal_args(step.args, options.hold)?,
+        false => step.args,
+    };
+    let execute = args.first().ok_or_else(|| SherlockError {
+        error: SherlockErrorType::CommandExecutionError(exec.to_string()),
+        traceback: String::from("The command is empty"),
+    })?;
+    let mut command = Command::new(execute);
+    command
+        .args(&args[1..])
+        .envs(options.env_with(step.env))
+        .stdin(Stdio::null())
+        .stderr(Stdio::null());
+    if let Some(cwd) = options.cwd {
+        command.current_dir(working_dir(cwd));
+    }
+    let output = command.output().map_err(|e| SherlockError {
+        error: SherlockErrorType::CommandExecutionError(execute.to_string()),
+        traceback: e.to_string(),
+    })?;
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    Ok(CommandOutput {
+        stdout: stdout.strip_suffix('\n').unwrap_or(&stdout).to_string(),
+        exit_code: output.status.code().unwrap_or(-1),
+    })
+}
+
+/// Starts `args` in its own session, detached from Sherlock
+pub fn spawn_detached(
+    args: &[String],
+    env: &[(String, String)],
+    cwd: Option<&str>,
+) -> Result<(), SherlockError> {
+    let execute = args.first().ok_or_else(|| SherlockError {
+        error: SherlockErrorType::CommandExecutionError(String::new()),
+        traceback: String::from("The command is empty"),
+    })?;
     let mut command = Command::new(execute);
     unsafe {
         command
-            .args(args)
+            .args(&args[1..])
+            .envs(env.iter().map(|(k, v)| (k, v)))
             .stdin(Stdio::null())
             .stdout(Stdio::null())
             .stderr(Stdio::null())
@@ -33,6 +283,9 @@ fn asynchronous_execution(execute: &str, args: Vec<&str>) -> Result<(), Sherlock
                 Ok(())
             });
     }
+    if let Some(cwd) = cwd {
+        command.current_dir(working_dir(cwd));
+    }
 
     command.spawn().map_err(|e| SherlockError {
         error: SherlockErrorType::CommandExecutionError(execute.to_string()),
@@ -41,3 +294,262 @@ fn asynchronous_execution(execute: &str, args: Vec<&str>) -> Result<(), Sherlock
 
     Ok(())
 }
+
+/// Splits a command into words like a POSIX shell, without any expansions:
+///
+/// - `'…'` keeps everything literally, `"…"` everything but `\"`, `\\`, `\$`
+///   and `` \` ``, and outside of quotes `\` escapes any character
+/// - `NAME=value` words in front of a command set environment variables
+/// - `&`, `;`, `&&` and `||` join commands, see [`Separator`]. They only count
+///   as words of their own, so `&` within a URL stays part of it.
+///
+/// Pipes and redirections need a shell and are rejected.
+pub fn parse_command(exec: &str) -> Result<Vec<Step>, String> {
+    let mut steps: Vec<Step> = Vec::new();
+    let mut current = Step {
+        env: Vec::new(),
+        args: Vec::new(),
+        then: Separator::End,
+    };
+    for word in split_words(exec)? {
+        let separator = match (word.quoted_from, word.text.as_str()) {
+            (None, "&") => Some(Separator::Background),
+            (None, ";") => Some(Separator::Sequence),
+            (None, "&&") => Some(Separator::And),
+            (None, "||") => Some(Separator::Or),
+            (None, text) if text.contains(['|', '<', '>']) => {
+                return Err(format!(
+                    "\"{}\" is only supported with \"shell\": true",
+                    word.text
+                ))
+            }
+            _ => None,
+        };
+        if let Some(separator) = separator {
+            if current.args.is_empty() {
+                return Err(format!("\"{}\" has to follow a command", word.text));
+            }
+            current.then = separator;
+            steps.push(std::mem::replace(
+                &mut current,
+                Step {
+                    env: Vec::new(),
+                    args: Vec::new(),
+                    then: Separator::End,
+                },
+            ));
+            continue;
+        }
+        match word.assignment() {
+            Some((name, value)) if current.args.is_empty() => current.env.push((name, value)),
+            _ => current.args.push(word.text),
+        }
+    }
+
+    if !current.args.is_empty() {
+        steps.push(current);
+    } else if !current.env.is_empty() {
+        return Err(String::from("The command only sets variables"));
+    } else if steps
+        .last()
+        .is_some_and(|last| matches!(last.then, Separator::And | Separator::Or))
+    {
+        return Err(String::from("The command ends with an operator"));
+    }
+    // A trailing '&' or ';' ends the chain like a line break
+    if let Some(last) = steps.last_mut() {
+        last.then = Separator::End;
+    }
+    Ok(steps)
+}
+
+struct Word {
+    text: String,
+    /// Byte index in `text` at which the first quoted or escaped part begins
+    quoted_from: Option<usize>,
+}
+impl Word {
+    /// Splits `NAME=value`, if the name is a valid, unquoted variable name
+    fn assignment(&self) -> Option<(String, String)> {
+        let (name, value) = self.text.split_once('=')?;
+        let valid = name
+            .chars()
+            .enumerate()
+            .all(|(i, c)| c == '_' || c.is_ascii_alphabetic() || (i > 0 && c.is_ascii_digit()));
+        (valid && !name.is_empty() && self.quoted_from.is_none_or(|q| q > name.len()))
+            .then(|| (name.to_string(), value.to_string()))
+    }
+}
+
+fn split_words(exec: &str) -> Result<Vec<Word>, String> {
+    let unterminated = || String::from("The command contains an unterminated quote");
+    let mut words = Vec::new();
+    let mut current: Option<Word> = None;
+    let mut chars = exec.chars().peekable();
+
+    while let Some(c) = chars.next() {
+        if c.is_whitespace() {
+            words.extend(current.take());
+            continue;
+        }
+        // Pipes and redirects end a word even without a space around them
+        if matches!(c, '|' | '<' | '>') {
+            let mut operator = String::new();
+            // A file descriptor in front belongs to the redirect, as in `2>`
+            match current.take() {
+                Some(word)
+                    if word.quoted_from.is_none()
+                        && (word.text == "&" || word.text.chars().all(|c| c.is_ascii_digit())) =>
+                {
+                    operator = word.text
+                }
+                word => words.extend(word),
+            }
+            operator.push(c);
+            while let Some(c) = chars.next_if(|c| matches!(c, '|' | '<' | '>' | '&')) {
+                operator.push(c);
+            }
+            words.push(Word {
+                text: operator,
+                quoted_from: None,
+            });
+            continue;
+        }
+        let word = current.get_or_insert_with(|| Word {
+            text: String::new(),
+            quoted_from: None,
+        });
+        let quote_start = word.text.len();
+        match c {
+            '\'' => {
+                word.quoted_from.get_or_insert(quote_start);
+                loop {
+                    match chars.next() {
+                        Some('\'') => break,
+                        Some(c) => word.text.push(c),
+                        None => return Err(unterminated()),
+                    }
+                }
+            }
+            '"' => {
+                word.quoted_from.get_or_insert(quote_start);
+                loop {
+                    match chars.next() {
+                        Some('"') => break,
+                        Some('\\') => match chars.next() {
+                            Some(c @ ('"' | '\\' | '$' | '`')) => word.text.push(c),
+                            Some('\n') => {}
+                            Some(c) => {
+                                word.text.push('\\');
+                                word.text.push(c);
+                            }
+                            None => return Err(unterminated()),
+                        },
+                        Some(c) => word.text.push(c),
+                        None => return Err(unterminated()),
+                    }
+                }
+            }
+            '\\' => {
+                word.quoted_from.get_or_insert(quote_start);
+                match chars.next() {
+                    // A line continuation
+                    Some('\n') => {}
+                    Some(c) => word.text.push(c),
+                    None => {}
+                }
+            }
+            c => word.text.push(c),
+        }
+    }
+    words.extend(current);
+    Ok(words)
+}
+
+/// Quotes `text` for a POSIX shell
+fn shell_quote(text: &str) -> String {
+    format!("'{}'", text.replace('\'', "'\\''"))
+}
+
+/// Turns the chain back into a shell script in which nothing is expanded
+fn to_script(steps: &[Step]) -> String {
+    let mut script = String::new();
+    for step in steps {
+        for (name, value) in step.env.iter() {
+            script.push_str(&format!("{}={} ", name, shell_quote(value)));
+        }
+        let args: Vec<String> = step.args.iter().map(|a| shell_quote(a)).collect();
+        script.push_str(&args.join(" "));
+        script.push_str(match step.then {
+            Separator::Background => " & ",
+            Separator::Sequence => "; ",
+            Separator::And => " && ",
+            Separator::Or => " || ",
+            Separator::End => "",
+        });
+    }
+    script
+}
+
+#[test]
+fn test_parse_command() {
+    let args = |exec: &str| -> Vec<Vec<String>> {
+        parse_command(exec)
+            .unwrap()
+            .into_iter()
+            .map(|s| s.args)
+            .collect()
+    };
+    assert_eq!(
+        args(r#"notify-send "Hello World" 'it''s' a\ b"#),
+        vec![vec!["notify-send", "Hello World", "its", "a b"]]
+    );
+    assert_eq!(
+        args("xdg-open https://example.com/?a=1&b=2"),
+        vec![vec!["xdg-open", "https://example.com/?a=1&b=2"]]
+    );
+    assert_eq!(args("a & b '&' c &"), vec![vec!["a"], vec!["b", "&", "c"]]);
+
+    let steps = parse_command("FOO=1 BAR='x y' app --opt=1 && next").unwrap();
+    assert_eq!(
+        steps[0].env,
+        vec![
+            (String::from("FOO"), String::from("1")),
+            (String::from("BAR"), String::from("x y"))
+        ]
+    );
+    assert_eq!(steps[0].args, vec!["app", "--opt=1"]);
+    assert_eq!(steps[0].then, Separator::And);
+    assert_eq!(steps[1].then, Separator::End);
+    // Quoted names are no assignments
+    assert_eq!(args("'FOO'=1 app"), vec![vec!["FOO=1", "app"]]);
+
+    assert!(parse_command("echo 'open").is_err());
+    assert!(parse_command("ls | grep x").is_err());
+    assert!(parse_command("ls|grep x").is_err());
+    assert!(parse_command("cmd 2>/dev/null").is_err());
+    assert!(parse_command("cmd >out").is_err());
+    assert!(parse_command("cmd<in").is_err());
+    assert_eq!(args("a||b"), vec![vec!["a"], vec!["b"]]);
+    assert_eq!(args("echo '|' \\>"), vec![vec!["echo", "|", ">"]]);
+    assert!(parse_command("&& ls").is_err());
+    assert!(parse_command("ls &&").is_err());
+
+    assert_eq!(
+        to_script(&parse_command("A=$x echo \"it's\" ; b").unwrap()),
+        "A='$x' 'echo' 'it'\\''s'; 'b'"
+    );
+}
+
+#[test]
+fn test_in_terminal() {
+    let args = vec![String::from("btop")];
+    assert_eq!(
+        in_terminal("alacritty -e", args.clone(), false).unwrap(),
+        vec!["alacritty", "-e", "btop"]
+    );
+    let held = in_terminal("kitty", args.clone(), true).unwrap();
+    assert_eq!(held[..3], ["kitty", "/bin/sh", "-c"]);
+    assert_eq!(held[4..], ["sh", "btop"]);
+    assert!(in_terminal("", args, false).is_none());
+}
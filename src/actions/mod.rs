@@ -1,67 +1,146 @@
 use std::collections::HashMap;
+use std::io::Write;
 
-use gio::glib::variant::ToVariant;
+use applaunch::LaunchOptions;
+use commandlaunch::CommandOptions;
+use gio::glib::{self, variant::ToVariant};
 use gtk4::prelude::WidgetExt;
 use teamslaunch::teamslaunch;
-use util::eval_exit;
+use util::{eval_exit, show_error};
 
 use crate::{
     g_subclasses::sherlock_row::SherlockRow,
     launcher::{audio_launcher::MusicPlayerLauncher, process_launcher::ProcessLauncher},
-    loader::launcher_loader::CounterReader,
-    ui::user::{display_next, display_raw},
+    loader::{
+        counter_loader::CounterReader, desktop_entry::FieldCodes, history_loader::HistoryReader,
+        learning_loader::QueryLearner, util::CommandArg,
+    },
+    ui::{
+        arg_view::display_args,
+        user::{display_next, display_raw},
+        util::set_search_text,
+    },
 };
 
 pub mod applaunch;
 pub mod commandlaunch;
+pub mod pipeline;
 pub mod teamslaunch;
 pub mod util;
 pub mod websearch;
 
 pub fn execute_from_attrs(row: &SherlockRow, attrs: &HashMap<String, String>) {
     //construct HashMap
-    let attrs: HashMap<String, String> = attrs
+    let mut attrs: HashMap<String, String> = attrs
         .into_iter()
         .map(|(k, v)| (k.to_string(), v.to_string()))
         .collect();
+    // The query the row was found with, added to the history once the action
+    // succeeded. Callbacks belong to an action that already added it.
+    if let Some((query, mode)) = row.search().filter(|_| !pipeline::in_callback()) {
+        attrs.entry(String::from("history_query")).or_insert(query);
+        attrs.entry(String::from("history_mode")).or_insert(mode);
+    }
 
     if let Some(method) = attrs.get("method") {
         match method.as_str() {
             "categories" => {
                 attrs.get("exec").map(|mode| {
+                    let keyword = attrs.get("keyword").map_or("", |s| s.as_str());
+                    learn(keyword, mode);
                     let _ = row.activate_action("win.switch-mode", Some(&mode.to_variant()));
                 });
             }
-            "app_launcher" => {
+            "history" => {
+                let query = attrs.get("query").cloned().unwrap_or_default();
+                let mode = attrs.get("mode").map_or("all", |s| s.as_str());
+                let _ = row.activate_action("win.switch-mode", Some(&mode.to_variant()));
+                // Switching modes clears the search bar once idle, so the query
+                // has to be inserted after that
+                glib::idle_add_local_once(move || set_search_text(&query));
+            }
+            "app_launcher" | "app_categories" => {
                 let exec = attrs.get("exec").map_or("", |s| s.as_str());
-                let _ = applaunch::applaunch(exec);
-                increment(&exec);
-                eval_exit();
+                let keyword = attrs.get("keyword").map_or("", |s| s.as_str());
+                let options = LaunchOptions {
+                    cwd: attrs.get("cwd").map(|s| s.as_str()),
+                    env: attrs
+                        .iter()
+                        .filter_map(|(k, v)| Some((k.strip_prefix("env:")?, v.as_str())))
+                        .collect(),
+                    launch_prefix: attrs.get("launch_prefix").map(|s| s.as_str()),
+                    terminal: attrs.contains_key("terminal"),
+                    codes: FieldCodes {
+                        name: attrs.get("name").map(|s| s.as_str()),
+                        icon: attrs.get("icon").map(|s| s.as_str()),
+                        desktop_file: attrs.get("desktop_file").map(|s| s.as_str()),
+                    },
+                };
+                match applaunch::applaunch(exec, &options) {
+                    Ok(_) => {
+                        increment(&exec);
+                        learn(keyword, exec);
+                        remember(&attrs);
+                        eval_exit();
+                    }
+                    Err(e) => show_error(row, e),
+                }
             }
             "web_launcher" => {
                 let query = attrs.get("keyword").map_or("", |s| s.as_str());
                 let engine = attrs.get("engine").map_or("", |s| s.as_str());
-                let _ = websearch::websearch(engine, query);
-                let exec = format!("websearch-{}", engine);
-                increment(&exec);
-                eval_exit();
+                match websearch::websearch(engine, query) {
+                    Ok(_) => {
+                        increment(&format!("websearch-{}", engine));
+                        remember(&attrs);
+                        eval_exit();
+                    }
+                    Err(e) => show_error(row, e),
+                }
             }
             "command" => {
                 let exec = attrs.get("exec").map_or("", |s| s.as_str());
                 let keyword = attrs.get("keyword").map_or("", |s| s.as_str());
-                let _ = commandlaunch::command_launch(exec, keyword);
-                increment(&exec);
-                eval_exit();
+                // Commands with arguments first ask for them
+                if let Some(args) = attrs
+                    .get("args")
+                    .and_then(|args| serde_json::from_str::<Vec<CommandArg>>(args).ok())
+                {
+                    display_args(row, &args, attrs.clone());
+                    return;
+                }
+                if pipeline::has_callbacks(&attrs) {
+                    pipeline::run_with_callbacks(row, attrs.clone());
+                    return;
+                }
+                let options = CommandOptions::from_attrs(&attrs);
+                let placeholders = pipeline::placeholders(&attrs);
+                let placeholders: Vec<(&str, &str)> = placeholders
+                    .iter()
+                    .map(|(k, v)| (k.as_str(), v.as_str()))
+                    .collect();
+                match commandlaunch::command_launch(exec, &placeholders, &options) {
+                    Ok(_) => {
+                        increment(&exec);
+                        learn(keyword, exec);
+                        remember(&attrs);
+                        eval_exit();
+                    }
+                    Err(e) => show_error(row, e),
+                }
             }
             "copy" => {
-                if let Some(field) = attrs.get("field") {
-                    if let Some(output) = attrs.get(field) {
-                        let _ = util::copy_to_clipboard(output.as_str());
+                let output = match attrs.get("field") {
+                    Some(field) => attrs.get(field),
+                    None => attrs.get("result"),
+                };
+                match output.map_or(Ok(()), |output| util::copy_to_clipboard(output)) {
+                    Ok(_) => {
+                        remember(&attrs);
+                        eval_exit();
                     }
-                } else if let Some(result) = attrs.get("result") {
-                    let _ = util::copy_to_clipboard(result.as_str());
+                    Err(e) => show_error(row, e),
                 }
-                eval_exit();
             }
             "print" => {
                 if let Some(field) = attrs.get("field") {
@@ -71,14 +150,17 @@ pub fn execute_from_attrs(row: &SherlockRow, attrs: &HashMap<String, String>) {
                 } else if let Some(result) = attrs.get("result") {
                     print!("{}", result);
                 }
+                let _ = std::io::stdout().flush();
+                remember(&attrs);
                 eval_exit();
             }
-            "teams_event" => {
-                if let Some(meeting) = attrs.get("meeting_url") {
-                    teamslaunch(meeting);
+            "teams_event" => match attrs.get("meeting_url").map_or(Ok(()), |m| teamslaunch(m)) {
+                Ok(_) => {
+                    remember(&attrs);
+                    eval_exit();
                 }
-                eval_exit();
-            }
+                Err(e) => show_error(row, e),
+            },
             "next" => {
                 let next_content = attrs
                     .get("next_content")
@@ -92,16 +174,24 @@ pub fn execute_from_attrs(row: &SherlockRow, attrs: &HashMap<String, String>) {
             }
             "play-pause" | "audio_sink" => {
                 if let Some(player) = attrs.get("player") {
-                    let _ = MusicPlayerLauncher::playpause(player);
+                    if let Err(e) = MusicPlayerLauncher::playpause(player) {
+                        show_error(row, e);
+                    }
                 }
             }
             "kill-process" => {
-                let _ = attrs
+                let killed = attrs
                     .get("parent-pid")
                     .and_then(|p| p.parse::<i32>().ok())
                     .zip(attrs.get("child-pid").and_then(|c| c.parse::<i32>().ok()))
-                    .map(|(ppid, cpid)| ProcessLauncher::kill((ppid, cpid)));
-                eval_exit();
+                    .map_or(Ok(()), |(ppid, cpid)| ProcessLauncher::kill((ppid, cpid)));
+                match killed {
+                    Ok(_) => {
+                        remember(&attrs);
+                        eval_exit();
+                    }
+                    Err(e) => show_error(row, e),
+                }
             }
             _ => {
                 if let Some(out) = attrs.get("result") {
@@ -125,3 +215,14 @@ fn increment(key: &str) {
         let _ = count_reader.increment(key);
     };
 }
+fn learn(query: &str, exec: &str) {
+    if let Ok(learner) = QueryLearner::shared() {
+        let _ = learner.learn(query, exec);
+    };
+}
+/// Adds the query the row was found with to the search history
+fn remember(attrs: &HashMap<String, String>) {
+    if let Some((query, mode)) = attrs.get("history_query").zip(attrs.get("history_mode")) {
+        let _ = HistoryReader::new().and_then(|reader| reader.push(query, mode));
+    }
+}
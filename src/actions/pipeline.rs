@@ -0,0 +1,198 @@
+use gio::{glib, prelude::*};
+use serde_json::Value;
+use std::cell::Cell;
+use std::collections::HashMap;
+
+use super::commandlaunch::{command_output, CommandOptions, CommandOutput};
+use super::util::{eval_exit, show_error};
+use super::{execute_from_attrs, increment, learn, remember};
+use crate::g_subclasses::sherlock_row::SherlockRow;
+
+thread_local! {
+    /// Set while the action of a callback is started
+    static IN_CALLBACK: Cell<bool> = const { Cell::new(false) };
+}
+
+/// Keys of the callbacks in the attrs of a row, see
+/// [`ActionCallbacks`](crate::loader::util::ActionCallbacks)
+const CALLBACKS: [&str; 3] = ["on_success", "on_failure", "then"];
+
+/// Methods a callback may use. The window is already closed when callbacks
+/// run, so methods that show something in it are not allowed.
+const CALLBACK_METHODS: [&str; 3] = ["command", "copy", "print"];
+
+/// Whether the current action is a callback. The window was already closed
+/// when its command started, so a callback must not close it again, in case
+/// Sherlock was opened in the meantime.
+pub fn in_callback() -> bool {
+    IN_CALLBACK.with(|flag| flag.get())
+}
+
+pub fn has_callbacks(attrs: &HashMap<String, String>) -> bool {
+    CALLBACKS.iter().any(|key| attrs.contains_key(*key))
+}
+
+/// Runs the command of `attrs` in the background, captures its output and then
+/// runs `on_success` or `on_failure`, followed by `then`. The window is closed
+/// right away, while Sherlock keeps running until the last callback is done.
+pub fn run_with_callbacks(row: &SherlockRow, attrs: HashMap<String, String>) {
+    let exec = attrs.get("exec").cloned().unwrap_or_default();
+    let command_attrs = attrs.clone();
+    let placeholders = placeholders(&attrs);
+
+    let hold = gio::Application::default().map(|app| app.hold());
+    // The window would otherwise be in the way, e.g. of a screenshot
+    eval_exit();
+
+    let row = row.clone();
+    glib::MainContext::default().spawn_local(async move {
+        let _hold = hold;
+        let result = gio::spawn_blocking(move || {
+            let placeholders: Vec<(&str, &str)> = placeholders
+                .iter()
+                .map(|(k, v)| (k.as_str(), v.as_str()))
+                .collect();
+            command_output(
+                &exec,
+                &placeholders,
+                &CommandOptions::from_attrs(&command_attrs),
+            )
+        })
+        .await;
+        let keyword = attrs.get("keyword").map_or("", |s| s.as_str());
+        let output = match result {
+            Ok(Ok(output)) => {
+                let exec = attrs.get("exec").map_or("", |s| s.as_str());
+                increment(exec);
+                learn(keyword, exec);
+                remember(&attrs);
+                output
+            }
+            // Commands that could not be started count as failed
+            Ok(Err(e)) => {
+                let output = CommandOutput {
+                    stdout: e.traceback.clone(),
+                    exit_code: 127,
+                };
+                if !attrs.contains_key("on_failure") {
+                    show_error(&row, e);
+                }
+                output
+            }
+            Err(_) => CommandOutput {
+                stdout: String::new(),
+                exit_code: -1,
+            },
+        };
+
+        let branch = match output.success() {
+            true => "on_success",
+            false => "on_failure",
+        };
+        for key in [branch, "then"] {
+            if let Some(mut step) = attrs
+                .get(key)
+                .and_then(|callback| callback_attrs(callback, keyword, &output))
+            {
+                // Callbacks can use the arguments of their command as well
+                for (arg, value) in attrs.iter().filter(|(k, _)| k.starts_with("arg:")) {
+                    step.entry(arg.clone()).or_insert(value.clone());
+                }
+                IN_CALLBACK.with(|flag| flag.set(true));
+                execute_from_attrs(&row, &step);
+                IN_CALLBACK.with(|flag| flag.set(false));
+            }
+        }
+    });
+}
+
+/// Values of `{keyword}`, `{output}`, `{exit_code}` and `{arg:<name>}` for the
+/// exec of a command, if the attrs hold them
+pub fn placeholders(attrs: &HashMap<String, String>) -> Vec<(String, String)> {
+    let args = attrs
+        .iter()
+        .filter(|(key, _)| key.starts_with("arg:"))
+        .map(|(key, value)| (format!("{{{}}}", key), value.clone()));
+    ["keyword", "output", "exit_code"]
+        .iter()
+        .filter_map(|key| Some((format!("{{{}}}", key), attrs.get(*key)?.clone())))
+        .chain(args)
+        .collect()
+}
+
+/// Turns a callback into the attrs of an action. A plain string is a command.
+/// `{output}` and `{exit_code}` refer to the command the callback belongs to.
+/// Returns `None` for methods that are not in [`CALLBACK_METHODS`].
+/// The exec keeps its placeholders, as they are only replaced once it is
+/// split into arguments.
+fn callback_attrs(
+    callback: &str,
+    keyword: &str,
+    output: &CommandOutput,
+) -> Option<HashMap<String, String>> {
+    let exit_code = output.exit_code.to_string();
+    let fill = |text: &str| {
+        text.replace("{output}", &output.stdout)
+            .replace("{exit_code}", &exit_code)
+    };
+    let mut attrs: HashMap<String, String> = match serde_json::from_str(callback).ok()? {
+        Value::String(exec) => HashMap::from([
+            (String::from("method"), String::from("command")),
+            (String::from("exec"), exec),
+        ]),
+        Value::Object(fields) => fields
+            .into_iter()
+            .map(|(key, value)| {
+                let value = match value {
+                    Value::String(text) if key == "exec" => text,
+                    Value::String(text) => fill(&text),
+                    // Nested callbacks are filled with their own command's output
+                    other => other.to_string(),
+                };
+                (key, value)
+            })
+            .collect(),
+        _ => return None,
+    };
+    if !CALLBACK_METHODS.contains(&attrs.get("method")?.as_str()) {
+        return None;
+    }
+    attrs.insert(String::from("output"), output.stdout.clone());
+    attrs.insert(String::from("exit_code"), exit_code);
+    attrs
+        .entry(String::from("keyword"))
+        .or_insert(keyword.to_string());
+    Some(attrs)
+}
+
+#[test]
+fn test_callback_attrs() {
+    let output = CommandOutput {
+        stdout: String::from("/tmp/shot.png"),
+        exit_code: 0,
+    };
+    let attrs = callback_attrs("\"xdg-open {output}\"", "", &output).unwrap();
+    assert_eq!(attrs["method"], "command");
+    assert_eq!(attrs["exec"], "xdg-open {output}");
+    assert_eq!(attrs["output"], "/tmp/shot.png");
+
+    let callback = r#"{"method": "copy", "result": "{output} ({exit_code})"}"#;
+    let attrs = callback_attrs(callback, "query", &output).unwrap();
+    assert_eq!(attrs["result"], "/tmp/shot.png (0)");
+    assert_eq!(attrs["keyword"], "query");
+
+    // Nested callbacks are left for their own command
+    let callback = r#"{"method": "command", "exec": "wc -c {output}", "on_success": {"method": "print", "result": "{output}"}}"#;
+    let attrs = callback_attrs(callback, "", &output).unwrap();
+    assert!(attrs["on_success"].contains("{output}"));
+    // Methods that need the window are rejected
+    let callback = r#"{"method": "next", "next_content": "{output}"}"#;
+    assert!(callback_attrs(callback, "", &output).is_none());
+    assert_eq!(
+        placeholders(&attrs)
+            .iter()
+            .find(|(k, _)| k == "{output}")
+            .map(|(_, v)| v.as_str()),
+        Some("/tmp/shot.png")
+    );
+}
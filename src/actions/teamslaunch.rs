@@ -1,40 +1,28 @@
-use crate::{actions::util::eval_exit, CONFIG};
-use std::{
-    os::unix::process::CommandExt,
-    process::{Command, Stdio},
+use crate::{
+    loader::{
+        desktop_entry::{expand_exec, FieldCodes},
+        util::{SherlockError, SherlockErrorType},
+    },
+    CONFIG,
 };
 
-pub fn teamslaunch(meeting_url: &str) {
-    if let Some(c) = CONFIG.get() {
-        let teams_command = c.default_apps.teams.clone();
-        let exec = teams_command.replace("{meeting_url}", meeting_url);
+use super::commandlaunch::spawn_detached;
 
-        let parts: Vec<String> = exec.split_whitespace().map(String::from).collect();
+pub fn teamslaunch(meeting_url: &str) -> Result<(), SherlockError> {
+    let c = CONFIG.get().ok_or_else(|| SherlockError {
+        error: SherlockErrorType::ConfigError(None),
+        traceback: String::new(),
+    })?;
+    let teams_command = &c.default_apps.teams;
+    let args = expand_exec(teams_command, &FieldCodes::default()).ok_or_else(|| SherlockError {
+        error: SherlockErrorType::CommandExecutionError(teams_command.clone()),
+        traceback: String::from("The command contains an unterminated quote"),
+    })?;
 
-        if parts.is_empty() {
-            eprintln!("Error: Command is empty");
-            eval_exit();
-        }
-
-        let mut command = Command::new(&parts[0]);
-        for arg in &parts[1..] {
-            if !arg.starts_with("%") {
-                command.arg(arg);
-            }
-        }
-
-        #[cfg(target_family = "unix")]
-        unsafe {
-            command
-                .stdin(Stdio::null())
-                .stdout(Stdio::null())
-                .stderr(Stdio::null())
-                .pre_exec(|| {
-                    nix::unistd::setsid().ok();
-                    Ok(())
-                });
-        }
-
-        let _output = command.spawn().expect("Failed to start the application");
-    }
+    // The URL is inserted after splitting, so it always stays one argument
+    let args: Vec<String> = args
+        .iter()
+        .map(|arg| arg.replace("{meeting_url}", meeting_url))
+        .collect();
+    spawn_detached(&args, &[], None)
 }
@@ -1,6 +1,13 @@
 use cli_clipboard::{ClipboardContext, ClipboardProvider};
+use gtk4::{prelude::*, ListBox};
+use std::collections::HashMap;
+use zbus::blocking::{Connection, Proxy};
+use zbus::zvariant::Value;
 
+use super::pipeline::in_callback;
+use crate::g_subclasses::sherlock_row::SherlockRow;
 use crate::loader::util::{SherlockError, SherlockErrorType};
+use crate::ui::tiles::Tile;
 use crate::ui::window::{destroy_window, hide_window};
 use crate::CONFIG;
 
@@ -21,6 +28,9 @@ pub fn read_from_clipboard() -> Result<String, SherlockError> {
     Ok(ctx.get_contents().unwrap_or_default().trim().to_string())
 }
 pub fn eval_exit() {
+    if in_callback() {
+        return;
+    }
     if let Some(c) = CONFIG.get() {
         match c.behavior.daemonize {
             true => hide_window(true),
@@ -30,3 +40,66 @@ pub fn eval_exit() {
         destroy_window();
     }
 }
+
+/// Reports an action that failed on `row`. While the window is shown, Sherlock
+/// stays open and the error is inserted as a tile below the row. Otherwise a
+/// desktop notification is sent, if `notify_errors` is enabled.
+pub fn show_error(row: &SherlockRow, error: SherlockError) {
+    if row.is_mapped() {
+        if let Some(results) = row.parent().and_downcast::<ListBox>() {
+            let (_, tiles) = Tile::error_tile(0, &vec![error], "🚨", "ERROR");
+            let index = row.index() + 1;
+            for (i, tile) in tiles.iter().enumerate() {
+                results.insert(tile, index + i as i32);
+            }
+            return;
+        }
+    }
+    if CONFIG.get().is_some_and(|c| c.behavior.notify_errors) {
+        if let Err(e) = notify_error(&error) {
+            eprintln!("{:?}", e);
+        }
+    }
+}
+
+/// Sends the error as a notification over the `org.freedesktop.Notifications`
+/// interface
+fn notify_error(error: &SherlockError) -> Result<(), SherlockError> {
+    let conn = Connection::session().map_err(|e| SherlockError {
+        error: SherlockErrorType::DBusConnectionError,
+        traceback: e.to_string(),
+    })?;
+    let proxy = Proxy::new(
+        &conn,
+        "org.freedesktop.Notifications",
+        "/org/freedesktop/Notifications",
+        "org.freedesktop.Notifications",
+    )
+    .map_err(|e| SherlockError {
+        error: SherlockErrorType::DBusMessageConstructError(String::from("Notify")),
+        traceback: e.to_string(),
+    })?;
+    let (name, message) = error.error.get_message();
+    let body = format!("{}\n{}", message, error.traceback.trim());
+    let actions: Vec<&str> = Vec::new();
+    let hints: HashMap<&str, Value> = HashMap::new();
+    proxy
+        .call_method(
+            "Notify",
+            &(
+                "Sherlock",
+                0u32,
+                "dialog-error",
+                name,
+                body.trim(),
+                actions,
+                hints,
+                -1i32,
+            ),
+        )
+        .map_err(|e| SherlockError {
+            error: SherlockErrorType::DBusMessageSendError(String::from("Notify")),
+            traceback: e.to_string(),
+        })?;
+    Ok(())
+}
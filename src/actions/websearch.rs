@@ -1,10 +1,12 @@
 use std::collections::HashMap;
+use std::fs;
 use std::process::Command;
 
 use crate::loader::application_loader::{get_applications_dir, get_desktop_files};
-use crate::loader::util::{read_lines, SherlockError, SherlockErrorType};
+use crate::loader::desktop_entry::{split_exec, DesktopFile};
+use crate::loader::util::{SherlockError, SherlockErrorType};
 
-use super::commandlaunch::command_launch;
+use super::commandlaunch::spawn_detached;
 
 pub fn websearch(engine: &str, query: &str) -> Result<(), SherlockError> {
     let engines: HashMap<&str, &str> = HashMap::from([
@@ -50,32 +52,39 @@ pub fn websearch(engine: &str, query: &str) -> Result<(), SherlockError> {
     let desktop_dirs = get_applications_dir();
     let desktop_files = get_desktop_files(desktop_dirs);
     let browser_file = desktop_files
-        .iter()
-        .find(|f| f.ends_with(&desktop_file))
+        .get(&desktop_file)
         .ok_or_else(|| SherlockError {
             error: SherlockErrorType::EnvVarNotFoundError("default browser".to_string()),
             traceback: String::new(),
         })?;
     // read default browser desktop file
-    let mut browser = read_lines(browser_file)
-        .map_err(|e| SherlockError {
-            error: SherlockErrorType::FileReadError(browser_file.clone()),
-            traceback: e.to_string(),
-        })?
-        .filter_map(Result::ok)
-        .find(|line| line.starts_with("Exec="))
-        .and_then(|line| line.strip_prefix("Exec=").map(|l| l.to_string()))
+    let content = fs::read_to_string(browser_file).map_err(|e| SherlockError {
+        error: SherlockErrorType::FileReadError(browser_file.clone()),
+        traceback: e.to_string(),
+    })?;
+    let browser = DesktopFile::parse(&content)
+        .entry()
+        .and_then(|entry| entry.string("Exec"))
         .ok_or_else(|| SherlockError {
             error: SherlockErrorType::FileParseError(browser_file.clone()),
             traceback: String::new(),
         })?;
+    let args = split_exec(&browser).ok_or_else(|| SherlockError {
+        error: SherlockErrorType::CommandExecutionError(browser.clone()),
+        traceback: String::from("The command contains an unterminated quote"),
+    })?;
 
-    let url = url_template.replace("{keyword}", query);
-    let command = if browser.contains("%u") {
-        browser.replace("%u", &url)
-    } else {
-        browser.push_str(&format!(" {}", url));
-        browser
-    };
-    command_launch(&command, "")
+    // The URL replaces the browser's URL field code, so no part of the query
+    // is ever parsed as a command
+    let mut url = Some(url_template.replace("{keyword}", query));
+    let mut args: Vec<String> = args
+        .into_iter()
+        .filter_map(|arg| match arg.as_str() {
+            "%u" | "%U" | "%f" | "%F" => url.take(),
+            _ if arg.len() == 2 && arg.starts_with('%') => None,
+            _ => Some(arg),
+        })
+        .collect();
+    args.extend(url);
+    spawn_detached(&args, &[], None)
 }
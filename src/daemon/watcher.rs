@@ -0,0 +1,250 @@
+use nix::errno::Errno;
+use nix::poll::{poll, PollFd, PollFlags, PollTimeout};
+use nix::sys::inotify::{AddWatchFlags, InitFlags, Inotify, InotifyEvent, WatchDescriptor};
+use std::collections::{HashMap, HashSet};
+use std::fs;
+use std::os::fd::AsFd;
+use std::path::{Path, PathBuf};
+
+use crate::loader::application_loader::get_applications_dir;
+use crate::loader::util::{SherlockError, SherlockErrorType};
+use crate::loader::Loader;
+use crate::CONFIG;
+
+/// Time in milliseconds to wait for further changes before the apps are
+/// updated, e.g. while a package manager installs several files
+const DEBOUNCE_MS: u16 = 300;
+
+/// Watches the application directories as well as the alias and ignore file,
+/// and updates the apps of the running launchers when they change.
+pub struct AppWatcher {
+    inotify: Inotify,
+    /// Watched directories that contain desktop files
+    app_dirs: HashMap<WatchDescriptor, PathBuf>,
+    /// Application directories that don't exist yet, e.g.
+    /// `~/.local/share/applications` before the first app is installed there
+    missing: HashSet<PathBuf>,
+    /// Watched nearest existing ancestors of the missing directories, to
+    /// notice when they are created
+    ancestors: HashMap<WatchDescriptor, PathBuf>,
+    /// Watched parent directories of the alias and ignore file. Editors often
+    /// replace files instead of writing to them, so the files can't be
+    /// watched directly.
+    config_dirs: HashMap<WatchDescriptor, PathBuf>,
+    config_files: HashSet<PathBuf>,
+}
+impl AppWatcher {
+    pub fn new() -> Result<Self, SherlockError> {
+        let config = CONFIG.get().ok_or_else(|| SherlockError {
+            error: SherlockErrorType::ConfigError(None),
+            traceback: String::new(),
+        })?;
+        let inotify = Inotify::init(InitFlags::IN_CLOEXEC).map_err(|e| SherlockError {
+            error: SherlockErrorType::FileWatchError(PathBuf::from("inotify")),
+            traceback: e.to_string(),
+        })?;
+        let mut watcher = AppWatcher {
+            inotify,
+            app_dirs: HashMap::new(),
+            missing: get_applications_dir().into_iter().collect(),
+            ancestors: HashMap::new(),
+            config_dirs: HashMap::new(),
+            config_files: HashSet::from([config.files.alias.clone(), config.files.ignore.clone()]),
+        };
+
+        // The apps were just loaded, so the files found now are not changed
+        watcher.watch_missing(&mut HashSet::new());
+        let parents: HashSet<PathBuf> = watcher
+            .config_files
+            .iter()
+            .filter_map(|f| f.parent().map(Path::to_path_buf))
+            .collect();
+        for dir in parents {
+            if let Ok(wd) = watcher.inotify.add_watch(&dir, config_flags()) {
+                watcher.config_dirs.insert(wd, dir);
+            }
+        }
+        Ok(watcher)
+    }
+
+    /// Blocks and updates the apps on every change
+    pub fn run(mut self) {
+        loop {
+            let mut changed: HashSet<PathBuf> = HashSet::new();
+            let mut full = false;
+
+            // Wait for the first change, then collect changes until things
+            // have been quiet for a moment
+            let mut timeout = PollTimeout::NONE;
+            loop {
+                let mut fds = [PollFd::new(self.inotify.as_fd(), PollFlags::POLLIN)];
+                match poll(&mut fds, timeout) {
+                    Ok(0) => break,
+                    Ok(_) => {}
+                    Err(Errno::EINTR) => continue,
+                    Err(e) => {
+                        eprintln!("Error: {:?}", e);
+                        return;
+                    }
+                }
+                match self.inotify.read_events() {
+                    Ok(events) => {
+                        for event in events {
+                            full |= self.handle(event, &mut changed);
+                        }
+                    }
+                    Err(e) => {
+                        eprintln!("Error: {:?}", e);
+                        return;
+                    }
+                }
+                timeout = PollTimeout::from(DEBOUNCE_MS);
+            }
+
+            // Nothing that affects the apps changed, e.g. only an unrelated
+            // file next to the alias file
+            if changed.is_empty() && !full {
+                continue;
+            }
+            if let Err(e) = Loader::refresh_app_indexes(&changed, full) {
+                eprintln!("Error: {:?}", e);
+            }
+        }
+    }
+
+    /// Records a changed desktop file in `changed`. Returns true if all apps
+    /// have to be read again.
+    fn handle(&mut self, event: InotifyEvent, changed: &mut HashSet<PathBuf>) -> bool {
+        if event.mask.contains(AddWatchFlags::IN_Q_OVERFLOW) {
+            return true;
+        }
+        if event.mask.contains(AddWatchFlags::IN_IGNORED) {
+            self.config_dirs.remove(&event.wd);
+            self.ancestors.remove(&event.wd);
+            // Removed application directories are waited for again
+            if let Some(dir) = self.app_dirs.remove(&event.wd) {
+                if get_applications_dir().contains(&dir) {
+                    self.missing.insert(dir.clone());
+                }
+                changed.insert(dir);
+            }
+            self.watch_missing(changed);
+            return false;
+        }
+        let name = match event.name {
+            Some(name) => name,
+            None => return false,
+        };
+        if let Some(dir) = self.config_dirs.get(&event.wd) {
+            return self.config_files.contains(&dir.join(name));
+        }
+        if self.ancestors.contains_key(&event.wd) && event.mask.contains(AddWatchFlags::IN_ISDIR) {
+            self.watch_missing(changed);
+        }
+        if let Some(dir) = self.app_dirs.get(&event.wd) {
+            let path = dir.join(name);
+            if event.mask.contains(AddWatchFlags::IN_ISDIR) {
+                if event
+                    .mask
+                    .intersects(AddWatchFlags::IN_CREATE | AddWatchFlags::IN_MOVED_TO)
+                {
+                    self.watch_app_dir(&path, changed);
+                } else {
+                    // Its apps are dropped as their files no longer exist
+                    changed.insert(path);
+                }
+            } else if path.extension().is_some_and(|ext| ext == "desktop") {
+                changed.insert(path);
+            }
+        }
+        false
+    }
+
+    /// Watches `dir` and all of its subdirectories. The desktop files in them
+    /// are added to `found`, as they may have been moved in with their
+    /// directory.
+    fn watch_app_dir(&mut self, dir: &Path, found: &mut HashSet<PathBuf>) {
+        if let Ok(wd) = self.inotify.add_watch(dir, app_flags()) {
+            self.app_dirs.insert(wd, dir.to_path_buf());
+        }
+        if let Ok(entries) = fs::read_dir(dir) {
+            for entry in entries.filter_map(Result::ok) {
+                let path = entry.path();
+                if path.is_dir() {
+                    self.watch_app_dir(&path, found);
+                } else if path.extension().is_some_and(|ext| ext == "desktop") {
+                    found.insert(path);
+                }
+            }
+        }
+    }
+
+    /// Watches the missing application directories that exist by now. For the
+    /// others, the nearest existing ancestor is watched until they show up.
+    fn watch_missing(&mut self, found: &mut HashSet<PathBuf>) {
+        let mut needed: HashSet<WatchDescriptor> = HashSet::new();
+        for dir in std::mem::take(&mut self.missing) {
+            // Repeated in case a directory is created before its ancestor is
+            // watched
+            loop {
+                if dir.is_dir() {
+                    self.watch_app_dir(&dir, found);
+                    break;
+                }
+                let ancestor = match dir.ancestors().skip(1).find(|a| a.is_dir()) {
+                    Some(ancestor) => ancestor.to_path_buf(),
+                    None => {
+                        self.missing.insert(dir);
+                        break;
+                    }
+                };
+                // An application directory keeps its own watch, which covers
+                // created directories as well
+                let wd = match self.app_dirs.iter().find(|(_, d)| **d == ancestor) {
+                    Some((wd, _)) => Ok(*wd),
+                    None => self.inotify.add_watch(&ancestor, ancestor_flags()),
+                };
+                if let Ok(wd) = wd {
+                    self.ancestors.insert(wd, ancestor.clone());
+                    needed.insert(wd);
+                }
+                if dir.ancestors().skip(1).find(|a| a.is_dir()) == Some(&ancestor) {
+                    self.missing.insert(dir);
+                    break;
+                }
+            }
+        }
+        // Ancestors of directories that exist by now are no longer watched,
+        // unless they hold desktop files themselves
+        let unneeded: Vec<WatchDescriptor> = self
+            .ancestors
+            .keys()
+            .filter(|wd| !needed.contains(*wd))
+            .copied()
+            .collect();
+        for wd in unneeded {
+            self.ancestors.remove(&wd);
+            if !self.app_dirs.contains_key(&wd) {
+                let _ = self.inotify.rm_watch(wd);
+            }
+        }
+    }
+}
+
+fn app_flags() -> AddWatchFlags {
+    AddWatchFlags::IN_CREATE
+        | AddWatchFlags::IN_DELETE
+        | AddWatchFlags::IN_CLOSE_WRITE
+        | AddWatchFlags::IN_MOVED_FROM
+        | AddWatchFlags::IN_MOVED_TO
+        | AddWatchFlags::IN_ONLYDIR
+}
+fn ancestor_flags() -> AddWatchFlags {
+    AddWatchFlags::IN_CREATE | AddWatchFlags::IN_MOVED_TO | AddWatchFlags::IN_ONLYDIR
+}
+fn config_flags() -> AddWatchFlags {
+    AddWatchFlags::IN_CLOSE_WRITE
+        | AddWatchFlags::IN_DELETE
+        | AddWatchFlags::IN_MOVED_TO
+        | AddWatchFlags::IN_ONLYDIR
+}
@@ -19,6 +19,12 @@ impl SherlockRow {
     pub fn set_shortcut(&self, shortcut: bool) {
         self.imp().shortcut.set(shortcut);
     }
+    pub fn set_search(&self, query: &str, mode: &str) {
+        *self.imp().search.borrow_mut() = Some((query.to_string(), mode.to_string()));
+    }
+    pub fn search(&self) -> Option<(String, String)> {
+        self.imp().search.borrow().clone()
+    }
 }
 
 impl Default for SherlockRow {
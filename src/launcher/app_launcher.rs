@@ -1,7 +1,12 @@
 use crate::loader::util::AppData;
 use std::collections::HashMap;
+use std::sync::{Arc, RwLock};
+
+/// Apps keyed by their desktop file ID. Shared with the app watcher, which
+/// keeps them up to date in daemon mode.
+pub type AppIndex = Arc<RwLock<HashMap<String, AppData>>>;
 
 #[derive(Clone, Debug)]
 pub struct App {
-    pub apps: HashMap<String, AppData>,
+    pub apps: AppIndex,
 }
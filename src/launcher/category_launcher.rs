@@ -1,7 +1,179 @@
 use crate::loader::util::AppData;
-use std::collections::HashMap;
+use std::collections::{BTreeMap, HashMap};
+
+use super::app_launcher::AppIndex;
 
 #[derive(Clone, Debug)]
 pub struct CategoryLauncher {
     pub categories: HashMap<String, AppData>,
 }
+
+/// Groups the apps by the `Categories` of their desktop files. A category is
+/// addressed by its path from the top level, e.g. `["AudioVideo", "Audio"]`,
+/// and holds the apps that are in every category of its path.
+#[derive(Clone, Debug)]
+pub struct AppCategoryLauncher {
+    /// Shared with the app launcher
+    pub apps: AppIndex,
+    /// Priority of the apps in a category. The index holds the one of the app
+    /// launcher.
+    pub priority: f32,
+}
+
+/// The main categories of the desktop menu spec with their display name and
+/// icon. `Audio` and `Video` are main categories as well, but always come with
+/// `AudioVideo`, so they are listed below it.
+const MAIN_CATEGORIES: [(&str, &str, &str); 11] = [
+    ("AudioVideo", "Multimedia", "applications-multimedia"),
+    ("Development", "Development", "applications-development"),
+    ("Education", "Education", "applications-education"),
+    ("Game", "Games", "applications-games"),
+    ("Graphics", "Graphics", "applications-graphics"),
+    ("Network", "Internet", "applications-internet"),
+    ("Office", "Office", "applications-office"),
+    ("Science", "Science", "applications-science"),
+    ("Settings", "Settings", "preferences-system"),
+    ("System", "System", "applications-system"),
+    ("Utility", "Utilities", "applications-utilities"),
+];
+
+/// Additional categories that name the toolkit or desktop of an app rather
+/// than what it is for
+const TOOLKIT_CATEGORIES: [&str; 8] = ["DDE", "GNOME", "GTK", "Java", "KDE", "Motif", "Qt", "XFCE"];
+
+impl AppCategoryLauncher {
+    /// The categories directly below `path` with the number of apps in each,
+    /// sorted by category. The top level lists the main categories, every
+    /// level below the additional categories that narrow the apps down
+    /// further.
+    pub fn children(apps: &HashMap<String, AppData>, path: &[&str]) -> Vec<(String, usize)> {
+        let mut counts: BTreeMap<&str, usize> = BTreeMap::new();
+        let mut total = 0;
+        for app in apps.values().filter(|app| in_path(app, path)) {
+            total += 1;
+            for category in app.categories.iter() {
+                let is_main = MAIN_CATEGORIES.iter().any(|(id, _, _)| id == category);
+                let listed = match path.is_empty() {
+                    true => is_main,
+                    false => {
+                        !is_main
+                            && !category.starts_with("X-")
+                            && !TOOLKIT_CATEGORIES.contains(&category.as_str())
+                            && !path.contains(&category.as_str())
+                    }
+                };
+                if listed {
+                    *counts.entry(category).or_default() += 1;
+                }
+            }
+        }
+        // Sub-categories that hold every app of their parent add nothing
+        counts
+            .into_iter()
+            .filter(|(_, count)| path.is_empty() || *count < total)
+            .map(|(category, count)| (category.to_string(), count))
+            .collect()
+    }
+    /// The apps in every category of `path`
+    pub fn apps_in(apps: &HashMap<String, AppData>, path: &[&str]) -> HashMap<String, AppData> {
+        apps.iter()
+            .filter(|(_, app)| in_path(app, path))
+            .map(|(id, app)| (id.clone(), app.clone()))
+            .collect()
+    }
+}
+
+fn in_path(app: &AppData, path: &[&str]) -> bool {
+    path.iter().all(|c| app.categories.iter().any(|a| a == c))
+}
+
+/// The name a category is shown with. Main categories have their own, all
+/// others are split at their capitals, e.g. `TextEditor` becomes `Text Editor`.
+pub fn category_name(category: &str) -> String {
+    if let Some((_, name, _)) = MAIN_CATEGORIES.iter().find(|(id, _, _)| *id == category) {
+        return name.to_string();
+    }
+    let mut name = String::with_capacity(category.len() + 4);
+    let mut prev: Option<char> = None;
+    for c in category.chars() {
+        if c.is_uppercase() && prev.is_some_and(|p| p.is_lowercase()) {
+            name.push(' ');
+        }
+        name.push(c);
+        prev = Some(c);
+    }
+    name
+}
+
+pub fn category_icon(category: &str) -> &'static str {
+    MAIN_CATEGORIES
+        .iter()
+        .find(|(id, _, _)| *id == category)
+        .map_or("folder", |(_, _, icon)| icon)
+}
+
+#[test]
+fn test_app_categories() {
+    let app = |categories: &[&str]| AppData {
+        name: None,
+        icon: String::new(),
+        exec: String::new(),
+        keywords: String::new(),
+        untranslated_name: None,
+        generic_name: None,
+        comment: None,
+        categories: categories.iter().map(|c| c.to_string()).collect(),
+        cwd: None,
+        actions: Vec::new(),
+        tag_start: None,
+        tag_end: None,
+        desktop_file: None,
+        env: HashMap::new(),
+        launch_prefix: None,
+        boost: 0.0,
+        shell: false,
+        terminal: false,
+        hold: false,
+        callbacks: Default::default(),
+        args: Vec::new(),
+        priority: 0.0,
+        frecency: 0.0,
+    };
+    let apps = HashMap::from([
+        (
+            String::from("vlc"),
+            app(&["AudioVideo", "Audio", "Video", "Qt"]),
+        ),
+        (String::from("mpv"), app(&["AudioVideo", "Video"])),
+        (
+            String::from("gedit"),
+            app(&["Utility", "TextEditor", "GTK"]),
+        ),
+        (
+            String::from("kate"),
+            app(&["Utility", "TextEditor", "X-KDE-Utilities"]),
+        ),
+    ]);
+
+    assert_eq!(
+        AppCategoryLauncher::children(&apps, &[]),
+        vec![
+            (String::from("AudioVideo"), 2),
+            (String::from("Utility"), 2)
+        ]
+    );
+    // Video holds both apps, so only Audio narrows them down
+    assert_eq!(
+        AppCategoryLauncher::children(&apps, &["AudioVideo"]),
+        vec![(String::from("Audio"), 1)]
+    );
+    assert!(AppCategoryLauncher::children(&apps, &["Utility"]).is_empty());
+    assert_eq!(
+        AppCategoryLauncher::apps_in(&apps, &["AudioVideo", "Audio"]).len(),
+        1
+    );
+
+    assert_eq!(category_name("Network"), "Internet");
+    assert_eq!(category_name("TextEditor"), "Text Editor");
+    assert_eq!(category_name("IDE"), "IDE");
+}
@@ -0,0 +1,4 @@
+#[derive(Clone, Debug)]
+pub struct HistoryLauncher {
+    pub icon: String,
+}
@@ -9,13 +9,16 @@ pub mod calc_launcher;
 pub mod category_launcher;
 pub mod clipboard_launcher;
 pub mod event_launcher;
+pub mod history_launcher;
 pub mod process_launcher;
+pub mod ranking;
 pub mod system_cmd_launcher;
 mod utils;
 pub mod web_launcher;
 
 use crate::{
     g_subclasses::sherlock_row::SherlockRow,
+    loader::learning_loader::QueryLearner,
     ui::tiles::{util::AsyncOptions, Tile},
 };
 
@@ -23,9 +26,10 @@ use app_launcher::App;
 use audio_launcher::MusicPlayerLauncher;
 use bulk_text_launcher::BulkText;
 use calc_launcher::Calculator;
-use category_launcher::CategoryLauncher;
+use category_launcher::{AppCategoryLauncher, CategoryLauncher};
 use clipboard_launcher::ClipboardLauncher;
 use event_launcher::EventLauncher;
+use history_launcher::HistoryLauncher;
 use process_launcher::ProcessLauncher;
 use system_cmd_launcher::SystemCommand;
 use web_launcher::Web;
@@ -33,6 +37,7 @@ use web_launcher::Web;
 #[derive(Clone, Debug)]
 pub enum LauncherType {
     CategoryLauncher(CategoryLauncher),
+    AppCategories(AppCategoryLauncher),
     App(App),
     Web(Web),
     Calc(Calculator),
@@ -42,6 +47,7 @@ pub enum LauncherType {
     EventLauncher(EventLauncher),
     MusicPlayerLauncher(MusicPlayerLauncher),
     ProcessLauncher(ProcessLauncher),
+    History(HistoryLauncher),
     Empty,
 }
 
@@ -60,6 +66,7 @@ pub struct Launcher {
     pub shortcut: bool,
     pub spawn_focus: bool,
     pub only_home: bool,
+    pub typo_tolerance: bool,
 }
 
 #[derive(Clone, Debug)]
@@ -71,19 +78,37 @@ pub struct ResultItem {
 
 impl Launcher {
     // TODO: tile method recreates already stored data...
-    pub fn get_patch(&self, keyword: &str) -> Vec<ResultItem> {
+    /// `path` is the part of a sub-mode after the launcher's alias, e.g.
+    /// `["AudioVideo"]` for `apps/AudioVideo`
+    pub fn get_patch(
+        &self,
+        keyword: &str,
+        learned: &HashMap<String, f32>,
+        path: &[&str],
+    ) -> Vec<ResultItem> {
         match &self.launcher_type {
-            LauncherType::CategoryLauncher(ctg) => Tile::app_tile(self, keyword, &ctg.categories),
-            LauncherType::App(app) => Tile::app_tile(self, keyword, &app.apps),
+            LauncherType::CategoryLauncher(ctg) => {
+                Tile::app_tile(self, keyword, &ctg.categories, learned)
+            }
+            LauncherType::AppCategories(ctg) => {
+                Tile::app_category_tile(self, keyword, ctg, path, learned)
+            }
+            LauncherType::App(app) => match app.apps.read() {
+                Ok(apps) => Tile::app_tile(self, keyword, &apps, learned),
+                Err(_) => Vec::new(),
+            },
             LauncherType::Web(web) => Tile::web_tile(self, keyword, &web),
             LauncherType::Calc(calc) => Tile::calc_tile(self, &calc, keyword),
             LauncherType::BulkText(bulk_text) => Tile::bulk_text_tile(&self, keyword, &bulk_text),
-            LauncherType::SystemCommand(cmd) => Tile::app_tile(self, keyword, &cmd.commands),
+            LauncherType::SystemCommand(cmd) => {
+                Tile::app_tile(self, keyword, &cmd.commands, learned)
+            }
             LauncherType::Clipboard((clp, calc)) => {
                 Tile::clipboard_tile(self, &clp, &calc, keyword)
             }
             LauncherType::EventLauncher(evl) => Tile::event_tile(self, keyword, evl),
             LauncherType::ProcessLauncher(proc) => Tile::process_tile(self, keyword, &proc),
+            LauncherType::History(history) => Tile::history_tile(self, keyword, history),
 
             _ => Vec::new(),
         }
@@ -91,9 +116,16 @@ impl Launcher {
     pub fn get_execs(&self) -> Option<HashSet<String>> {
         // NOTE: make a function to check for exec changes in the caching algorithm
         match &self.launcher_type {
-            LauncherType::App(app) => {
-                let execs: HashSet<String> =
-                    app.apps.iter().map(|(_, v)| v.exec.to_string()).collect();
+            LauncherType::App(App { apps })
+            | LauncherType::AppCategories(AppCategoryLauncher { apps, .. }) => {
+                let apps = apps.read().ok()?;
+                let execs: HashSet<String> = apps
+                    .values()
+                    .flat_map(|v| {
+                        std::iter::once(v.exec.to_string())
+                            .chain(v.actions.iter().map(|a| a.exec.to_string()))
+                    })
+                    .collect();
                 Some(execs)
             }
             LauncherType::Web(web) => {
@@ -160,7 +192,19 @@ impl Launcher {
 
 pub fn construct_tiles(keyword: &str, launchers: &[Launcher], mode: &str) -> Vec<ResultItem> {
     let mut results = Vec::new();
-    let sel_mode = mode.trim();
+    // Sub-modes like 'apps/AudioVideo' belong to the launcher of their alias
+    let (sel_mode, path): (&str, Vec<&str>) = match mode.trim().split_once('/') {
+        Some((alias, path)) => (alias, path.split('/').filter(|p| !p.is_empty()).collect()),
+        None => (mode.trim(), Vec::new()),
+    };
+
+    // Boosts for results that were previously picked for this query
+    let learned: HashMap<String, f32> = match keyword.trim().is_empty() {
+        true => HashMap::new(),
+        false => QueryLearner::shared()
+            .and_then(|learner| learner.boosts(keyword))
+            .unwrap_or_default(),
+    };
     for launcher in launchers.iter() {
         let alias = launcher.alias.as_deref().unwrap_or("all");
 
@@ -169,7 +213,7 @@ pub fn construct_tiles(keyword: &str, launchers: &[Launcher], mode: &str) -> Vec
         }
 
         if alias == sel_mode || sel_mode == "all" {
-            let result = launcher.get_patch(keyword);
+            let result = launcher.get_patch(keyword, &learned, &path);
             results.extend(result);
         }
     }
@@ -0,0 +1,105 @@
+use gtk4::prelude::WidgetExt;
+
+use crate::g_subclasses::sherlock_row::SherlockRow;
+use crate::loader::util::ConfigRanking;
+use crate::CONFIG;
+
+/// The components a result's position is computed from. Results are sorted in
+/// ascending order of their total, so the priority pushes a result down while
+/// every other component pulls it up. Each component is scaled by its weight
+/// from the `[ranking]` section of the config. The components other than the
+/// priority only reorder results of the same priority: together they never
+/// reach a whole priority level.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct Score {
+    /// Priority of the launcher or entry, as set in `fallback.json`
+    pub priority: f32,
+    /// How well the query matched, in `0.0..=1.0`
+    pub quality: f32,
+    /// How often and how recently the result was launched, in `0.0..1.0`
+    pub frecency: f32,
+    /// How often the result was picked for similar queries, in `0.0..1.0`
+    pub learned: f32,
+}
+impl Score {
+    pub fn new(priority: f32) -> Self {
+        Score {
+            priority,
+            ..Default::default()
+        }
+    }
+    pub fn total(&self) -> f32 {
+        self.total_with(&weights())
+    }
+    fn total_with(&self, w: &ConfigRanking) -> f32 {
+        w.priority * self.priority - self.adjustment(w)
+    }
+    /// The weighted match, frecency and learned values, mapped into `0.0..1.0`
+    fn adjustment(&self, w: &ConfigRanking) -> f32 {
+        let sum = (w.match_quality * self.quality
+            + w.frecency * self.frecency
+            + w.learned * self.learned)
+            .max(0.0);
+        sum / (sum + 1.0)
+    }
+    /// Describes how the total came about, e.g. for the ranking tooltip
+    pub fn explain(&self) -> String {
+        let w = weights();
+        format!(
+            "score {:.3}\n  priority  {:>6.3} × {}\n− adjusted  {:>6.3} from\n  match     {:>6.3} × {}\n  frecency  {:>6.3} × {}\n  learned   {:>6.3} × {}",
+            self.total_with(&w),
+            self.priority,
+            w.priority,
+            self.adjustment(&w),
+            self.quality,
+            w.match_quality,
+            self.frecency,
+            w.frecency,
+            self.learned,
+            w.learned,
+        )
+    }
+    /// Returns the total and, if ranking explanations are enabled, shows the
+    /// breakdown as the row's tooltip.
+    pub fn apply(&self, row: &SherlockRow) -> f32 {
+        if CONFIG.get().is_some_and(|c| c.ranking.explain) {
+            row.set_tooltip_text(Some(&self.explain()));
+        }
+        self.total()
+    }
+}
+
+fn weights() -> ConfigRanking {
+    CONFIG.get().map(|c| c.ranking).unwrap_or_default()
+}
+
+#[test]
+fn test_score_total() {
+    let weights = ConfigRanking::default();
+    let unused = Score {
+        priority: 2.0,
+        quality: 0.5,
+        ..Default::default()
+    };
+    let used = Score {
+        frecency: 0.5,
+        ..unused
+    };
+    assert!(used.total_with(&weights) < unused.total_with(&weights));
+
+    // Even the strongest match never overtakes a launcher of a lower priority
+    let priority_one = Score::new(1.0);
+    let best = Score {
+        priority: 2.0,
+        quality: 1.0,
+        frecency: 0.99,
+        learned: 0.99,
+    };
+    assert!(best.total_with(&weights) > priority_one.total_with(&weights));
+    let weights = ConfigRanking {
+        match_quality: 10.0,
+        ..weights
+    };
+    assert!(best.total_with(&weights) > priority_one.total_with(&weights));
+    assert!(used.total_with(&weights) < unused.total_with(&weights));
+}
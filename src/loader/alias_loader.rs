@@ -0,0 +1,129 @@
+use glob::{MatchOptions, Pattern};
+use std::collections::HashMap;
+use std::fs::File;
+use std::path::Path;
+
+use super::util::{SherlockAlias, SherlockError, SherlockErrorType};
+
+/// The parsed `sherlock_alias.json` file. Its keys are desktop file IDs, app
+/// names or glob patterns matching either of them.
+#[derive(Debug, Default)]
+pub struct AliasRules {
+    exact: HashMap<String, SherlockAlias>,
+    /// Sorted from the least to the most specific (longest) pattern
+    globs: Vec<(Pattern, SherlockAlias)>,
+}
+impl AliasRules {
+    /// Reads the aliases from `path`. A missing file means that there are no
+    /// aliases.
+    pub fn read(path: &Path) -> Result<Self, SherlockError> {
+        let parse_error = |e: String| SherlockError {
+            error: SherlockErrorType::FileReadError(path.to_path_buf()),
+            traceback: e,
+        };
+        let aliases: HashMap<String, SherlockAlias> = match File::open(path) {
+            Ok(f) => simd_json::from_reader(f).map_err(|e| parse_error(e.to_string()))?,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => Default::default(),
+            Err(e) => return Err(parse_error(e.to_string())),
+        };
+        Ok(AliasRules::new(aliases))
+    }
+    pub fn new(aliases: HashMap<String, SherlockAlias>) -> Self {
+        let mut rules = AliasRules::default();
+        for (key, alias) in aliases {
+            let is_glob = key.contains(['*', '?', '[']);
+            match Pattern::new(&key) {
+                Ok(pattern) if is_glob => rules.globs.push((pattern, alias)),
+                _ => {
+                    rules.exact.insert(key, alias);
+                }
+            }
+        }
+        rules.globs.sort_by(|(a, _), (b, _)| {
+            let (a, b) = (a.as_str(), b.as_str());
+            a.len().cmp(&b.len()).then(a.cmp(b))
+        });
+        rules
+    }
+    /// Merges every alias that applies to the app with the desktop file ID
+    /// `id` and the given names. Fields of more specific keys win: exact IDs
+    /// over exact names over glob patterns, and longer patterns over shorter
+    /// ones.
+    pub fn resolve(&self, id: &str, names: &[&str]) -> Option<SherlockAlias> {
+        let options = MatchOptions {
+            case_sensitive: false,
+            ..Default::default()
+        };
+        let globs = self
+            .globs
+            .iter()
+            .filter(|(pattern, _)| {
+                std::iter::once(id)
+                    .chain(names.iter().copied())
+                    .any(|text| pattern.matches_with(text, options))
+            })
+            .map(|(_, alias)| alias);
+        let exact = names
+            .iter()
+            .rev()
+            .chain(std::iter::once(&id))
+            .filter_map(|key| self.exact.get(*key));
+
+        globs.chain(exact).fold(None, |merged, alias| match merged {
+            Some(merged) => Some(alias.clone().or(merged)),
+            None => Some(alias.clone()),
+        })
+    }
+}
+
+impl SherlockAlias {
+    /// Takes every field that is not set in `self` from `other`
+    fn or(self, other: SherlockAlias) -> SherlockAlias {
+        let mut env = other.env;
+        env.extend(self.env);
+        SherlockAlias {
+            name: self.name.or(other.name),
+            icon: self.icon.or(other.icon),
+            exec: self.exec.or(other.exec),
+            keywords: self.keywords.or(other.keywords),
+            boost: self.boost.or(other.boost),
+            hidden: self.hidden.or(other.hidden),
+            env,
+            cwd: self.cwd.or(other.cwd),
+            terminal: self.terminal.or(other.terminal),
+            launch_prefix: self.launch_prefix.or(other.launch_prefix),
+        }
+    }
+}
+
+#[test]
+fn test_alias_rules() {
+    let alias = |name: &str, boost: Option<f32>| SherlockAlias {
+        name: Some(name.to_string()),
+        boost,
+        ..Default::default()
+    };
+    let rules = AliasRules::new(HashMap::from([
+        (String::from("org.kde.*"), alias("KDE", Some(1.0))),
+        (
+            String::from("org.kde.konsole*"),
+            alias("Konsole (KDE)", None),
+        ),
+        (String::from("Konsole"), alias("Terminal", None)),
+        (String::from("firefox.desktop"), alias("Browser", None)),
+    ]));
+
+    // The more specific key wins, other fields are kept
+    let konsole = rules
+        .resolve("org.kde.konsole.desktop", &["Konsole"])
+        .unwrap();
+    assert_eq!(konsole.name.as_deref(), Some("Terminal"));
+    assert_eq!(konsole.boost, Some(1.0));
+    let dolphin = rules.resolve("org.kde.dolphin.desktop", &["Dolphin"]);
+    assert_eq!(dolphin.unwrap().name.as_deref(), Some("KDE"));
+
+    // Keys by ID survive renames and translations
+    let firefox = rules.resolve("firefox.desktop", &["Firefox-Webbrowser", "Firefox"]);
+    assert_eq!(firefox.unwrap().name.as_deref(), Some("Browser"));
+    assert!(rules.resolve("gimp.desktop", &["GIMP"]).is_none());
+}
@@ -0,0 +1,308 @@
+use std::collections::HashMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::time::UNIX_EPOCH;
+
+use super::util::{tmp_path, AppAction, AppData};
+
+/// Identifies a Sherlock app cache
+const MAGIC: &[u8; 8] = b"SHLKAPPS";
+/// Has to be increased whenever the layout of the cache or of [`AppData`]
+/// changes. Caches of other versions are discarded.
+const FORMAT_VERSION: u32 = 3;
+
+/// A desktop file as it was when the cache was written
+#[derive(Clone, Debug, PartialEq)]
+pub struct FileStamp {
+    pub path: PathBuf,
+    /// Modification time in nanoseconds since the epoch
+    pub mtime: u64,
+}
+impl FileStamp {
+    pub fn new(path: &Path) -> Self {
+        FileStamp {
+            path: path.to_path_buf(),
+            mtime: mtime(path).unwrap_or(0),
+        }
+    }
+}
+
+/// The app cache. Only valid if it was written with the same settings, see
+/// [`config_hash`], and only for the desktop files whose stamps still match.
+#[derive(Debug, Default, PartialEq)]
+pub struct AppCache {
+    pub config_hash: u64,
+    /// Stamps of all desktop files that were read, keyed by desktop file ID.
+    /// This includes files that did not turn into an app, e.g. hidden ones.
+    pub files: HashMap<String, FileStamp>,
+    /// Apps keyed by desktop file ID
+    pub apps: HashMap<String, AppData>,
+}
+impl AppCache {
+    /// Reads the cache at `path`. Returns `None` if it doesn't exist, is
+    /// damaged or was written by another format version.
+    pub fn read(path: &Path) -> Option<Self> {
+        let buf = fs::read(path).ok()?;
+        AppCache::decode(&buf)
+    }
+    pub fn write(&self, path: &Path) -> std::io::Result<()> {
+        let tmp_path = tmp_path(path);
+        fs::write(&tmp_path, self.encode())
+            .and_then(|_| fs::rename(&tmp_path, path))
+            .inspect_err(|_| {
+                let _ = fs::remove_file(&tmp_path);
+            })
+    }
+
+    pub fn encode(&self) -> Vec<u8> {
+        let mut w = Writer(Vec::with_capacity(64 * 1024));
+        w.0.extend_from_slice(MAGIC);
+        w.u32(FORMAT_VERSION);
+        w.u64(self.config_hash);
+        w.u32(self.files.len() as u32);
+        for (id, stamp) in self.files.iter() {
+            w.str(id);
+            w.str(&stamp.path.to_string_lossy());
+            w.u64(stamp.mtime);
+        }
+        w.u32(self.apps.len() as u32);
+        for (id, app) in self.apps.iter() {
+            w.str(id);
+            w.opt(&app.name);
+            w.str(&app.icon);
+            w.str(&app.exec);
+            w.str(&app.keywords);
+            w.opt(&app.untranslated_name);
+            w.opt(&app.generic_name);
+            w.opt(&app.comment);
+            w.u32(app.categories.len() as u32);
+            for category in app.categories.iter() {
+                w.str(category);
+            }
+            w.opt(&app.cwd);
+            w.u32(app.actions.len() as u32);
+            for action in app.actions.iter() {
+                w.str(&action.name);
+                w.str(&action.exec);
+                w.opt(&action.icon);
+            }
+            w.opt(&app.tag_start);
+            w.opt(&app.tag_end);
+            w.opt(
+                &app.desktop_file
+                    .as_ref()
+                    .map(|p| p.to_string_lossy().into()),
+            );
+            w.u32(app.env.len() as u32);
+            for (key, value) in app.env.iter() {
+                w.str(key);
+                w.str(value);
+            }
+            w.opt(&app.launch_prefix);
+            w.u32(app.boost.to_bits());
+            w.bool(app.terminal);
+        }
+        w.0
+    }
+
+    pub fn decode(buf: &[u8]) -> Option<Self> {
+        let mut r = Reader { buf, pos: 0 };
+        if r.take(MAGIC.len())? != MAGIC || r.u32()? != FORMAT_VERSION {
+            return None;
+        }
+        let config_hash = r.u64()?;
+        let mut files = HashMap::new();
+        for _ in 0..r.u32()? {
+            let id = r.str()?;
+            let stamp = FileStamp {
+                path: PathBuf::from(r.str()?),
+                mtime: r.u64()?,
+            };
+            files.insert(id, stamp);
+        }
+        let mut apps = HashMap::new();
+        for _ in 0..r.u32()? {
+            let id = r.str()?;
+            let app = AppData {
+                name: r.opt()?,
+                icon: r.str()?,
+                exec: r.str()?,
+                keywords: r.str()?,
+                untranslated_name: r.opt()?,
+                generic_name: r.opt()?,
+                comment: r.opt()?,
+                categories: (0..r.u32()?).map(|_| r.str()).collect::<Option<_>>()?,
+                cwd: r.opt()?,
+                actions: (0..r.u32()?)
+                    .map(|_| {
+                        Some(AppAction {
+                            name: r.str()?,
+                            exec: r.str()?,
+                            icon: r.opt()?,
+                            frecency: 0.0,
+                        })
+                    })
+                    .collect::<Option<_>>()?,
+                tag_start: r.opt()?,
+                tag_end: r.opt()?,
+                desktop_file: r.opt()?.map(PathBuf::from),
+                env: (0..r.u32()?)
+                    .map(|_| Some((r.str()?, r.str()?)))
+                    .collect::<Option<_>>()?,
+                launch_prefix: r.opt()?,
+                boost: f32::from_bits(r.u32()?),
+                shell: false,
+                terminal: r.bool()?,
+                hold: false,
+                callbacks: Default::default(),
+                args: Vec::new(),
+                priority: 0.0,
+                frecency: 0.0,
+            };
+            apps.insert(id, app);
+        }
+        (r.pos == buf.len()).then_some(AppCache {
+            config_hash,
+            files,
+            apps,
+        })
+    }
+}
+
+/// Hashes everything besides the desktop files themselves that influences the
+/// cached apps. Uses FNV-1a, as the hash has to be stable between builds.
+pub fn config_hash(parts: &[&str]) -> u64 {
+    let mut hash: u64 = 0xcbf29ce484222325;
+    for part in parts {
+        // The separator keeps ("ab", "c") and ("a", "bc") apart
+        for byte in part.bytes().chain(std::iter::once(0)) {
+            hash ^= byte as u64;
+            hash = hash.wrapping_mul(0x100000001b3);
+        }
+    }
+    hash
+}
+
+/// Modification time of `path` in nanoseconds since the epoch
+pub fn mtime(path: &Path) -> Option<u64> {
+    let modified = fs::metadata(path).and_then(|m| m.modified()).ok()?;
+    let nanos = modified.duration_since(UNIX_EPOCH).ok()?.as_nanos();
+    Some(nanos as u64)
+}
+
+struct Writer(Vec<u8>);
+impl Writer {
+    fn u32(&mut self, value: u32) {
+        self.0.extend_from_slice(&value.to_le_bytes());
+    }
+    fn u64(&mut self, value: u64) {
+        self.0.extend_from_slice(&value.to_le_bytes());
+    }
+    fn str(&mut self, value: &str) {
+        self.u32(value.len() as u32);
+        self.0.extend_from_slice(value.as_bytes());
+    }
+    fn bool(&mut self, value: bool) {
+        self.0.push(value as u8);
+    }
+    fn opt(&mut self, value: &Option<String>) {
+        match value {
+            Some(value) => {
+                self.0.push(1);
+                self.str(value);
+            }
+            None => self.0.push(0),
+        }
+    }
+}
+
+struct Reader<'a> {
+    buf: &'a [u8],
+    pos: usize,
+}
+impl<'a> Reader<'a> {
+    fn take(&mut self, len: usize) -> Option<&'a [u8]> {
+        let bytes = self.buf.get(self.pos..self.pos.checked_add(len)?)?;
+        self.pos += len;
+        Some(bytes)
+    }
+    fn u32(&mut self) -> Option<u32> {
+        Some(u32::from_le_bytes(self.take(4)?.try_into().ok()?))
+    }
+    fn u64(&mut self) -> Option<u64> {
+        Some(u64::from_le_bytes(self.take(8)?.try_into().ok()?))
+    }
+    fn str(&mut self) -> Option<String> {
+        let len = self.u32()? as usize;
+        String::from_utf8(self.take(len)?.to_vec()).ok()
+    }
+    fn bool(&mut self) -> Option<bool> {
+        match self.take(1)?[0] {
+            0 => Some(false),
+            1 => Some(true),
+            _ => None,
+        }
+    }
+    fn opt(&mut self) -> Option<Option<String>> {
+        match self.take(1)?[0] {
+            0 => Some(None),
+            1 => self.str().map(Some),
+            _ => None,
+        }
+    }
+}
+
+#[test]
+fn test_app_cache_roundtrip() {
+    let app = AppData {
+        name: Some(String::from("Firefox")),
+        icon: String::from("firefox"),
+        exec: String::from("firefox %u"),
+        keywords: String::from("web;browser"),
+        untranslated_name: None,
+        generic_name: Some(String::from("Web Browser")),
+        comment: None,
+        categories: vec![String::from("Network")],
+        cwd: None,
+        actions: vec![AppAction {
+            name: String::from("New Private Window"),
+            exec: String::from("firefox --private-window %u"),
+            icon: None,
+            frecency: 0.0,
+        }],
+        tag_start: None,
+        tag_end: None,
+        desktop_file: Some(PathBuf::from("/usr/share/applications/firefox.desktop")),
+        env: HashMap::from([(String::from("MOZ_ENABLE_WAYLAND"), String::from("1"))]),
+        launch_prefix: Some(String::new()),
+        boost: 1.5,
+        shell: false,
+        terminal: true,
+        hold: false,
+        callbacks: Default::default(),
+        args: Vec::new(),
+        priority: 0.0,
+        frecency: 0.0,
+    };
+    let cache = AppCache {
+        config_hash: config_hash(&["kitty", "de_DE"]),
+        files: HashMap::from([(
+            String::from("firefox.desktop"),
+            FileStamp {
+                path: PathBuf::from("/usr/share/applications/firefox.desktop"),
+                mtime: 42,
+            },
+        )]),
+        apps: HashMap::from([(String::from("firefox.desktop"), app)]),
+    };
+    let buf = cache.encode();
+    assert_eq!(AppCache::decode(&buf), Some(cache));
+
+    // Truncated caches and caches of other versions are rejected
+    assert_eq!(AppCache::decode(&buf[..buf.len() - 1]), None);
+    let mut other_version = buf.clone();
+    other_version[MAGIC.len()] += 1;
+    assert_eq!(AppCache::decode(&other_version), None);
+
+    assert_ne!(config_hash(&["ab", "c"]), config_hash(&["a", "bc"]));
+}
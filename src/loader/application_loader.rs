@@ -1,24 +1,34 @@
-use glob::Pattern;
 use rayon::prelude::*;
-use regex::Regex;
-use simd_json;
 use std::collections::{HashMap, HashSet};
 use std::env;
-use std::fs::{self, File};
+use std::fs;
 use std::path::{Path, PathBuf};
-use std::time::SystemTime;
+use std::sync::{Arc, Mutex, RwLock, Weak};
 
+use super::alias_loader::AliasRules;
+use super::app_cache::{config_hash, mtime, AppCache, FileStamp};
+use super::counter_loader::CounterReader;
+use super::desktop_entry::{current_desktops, DesktopFile, Locale};
+use super::ignore_loader::{IgnoreRules, IgnoreTarget};
 use super::util::{SherlockError, SherlockErrorType};
 use super::{util, Loader};
+use crate::launcher::app_launcher::AppIndex;
 use crate::CONFIG;
-use util::{parse_priority, read_file, read_lines, AppData, SherlockAlias};
+use util::{frecency_score, read_file, AppAction, AppData};
+
+/// Apps of the loaded app launchers with their priority, kept up to date by the
+/// app watcher. Indexes of launchers that were dropped, e.g. after a reload,
+/// are removed on the next refresh.
+static APP_INDEXES: Mutex<Vec<(WeakAppIndex, f32)>> = Mutex::new(Vec::new());
+type WeakAppIndex = Weak<RwLock<HashMap<String, AppData>>>;
 
 impl Loader {
+    /// Reads the given desktop files, keyed by their desktop file ID, or all
+    /// installed ones. Apps are keyed by their desktop file ID as well.
     pub fn load_applications_from_disk(
-        applications: Option<HashSet<PathBuf>>,
+        applications: Option<HashMap<String, PathBuf>>,
         priority: f32,
-        counts: HashMap<String, f32>,
-        decimals: i32,
+        frecency: &HashMap<String, f32>,
     ) -> Result<HashMap<String, AppData>, SherlockError> {
         let config = CONFIG.get().ok_or(SherlockError {
             error: SherlockErrorType::ConfigError(None),
@@ -28,45 +38,20 @@ impl Loader {
         // Define required paths for application parsing
         let system_apps = get_applications_dir();
 
-        // Parse needed fields from the '.desktop'
-        let (name_re, icon_re, exec_re, display_re, terminal_re, keywords_re) =
-            get_regex_patterns().map_err(|e| return e)?;
+        // Entries can be limited to certain desktop environments
+        let desktops = current_desktops();
 
-        let parse_field = |content: &str, regex: &Regex| {
-            regex
-                .captures(content)
-                .and_then(|caps| caps.get(1).map(|m| m.as_str().to_string()))
-                .unwrap_or_default()
-        };
+        // Translated keys are picked by the user's locale
+        let locale = Locale::current();
 
         // Parse user-specified 'sherlockignore' file
-        let ignore_apps: Vec<Pattern> = match read_lines(&config.files.ignore) {
-            Ok(lines) => lines
-                .map_while(Result::ok)
-                .filter_map(|line| Pattern::new(&line.to_lowercase()).ok())
-                .collect(),
-            Err(e) if e.kind() == std::io::ErrorKind::NotFound => Default::default(),
-            Err(e) => Err(SherlockError {
-                error: SherlockErrorType::FileReadError(config.files.ignore.clone()),
-                traceback: e.to_string(),
-            })?,
-        };
+        let ignore_rules = IgnoreRules::read(&config.files.ignore)?;
 
         // Parse user-specified 'sherlock_alias.json' file
-        let aliases: HashMap<String, SherlockAlias> = match File::open(&config.files.alias) {
-            Ok(f) => simd_json::from_reader(f).map_err(|e| SherlockError {
-                error: SherlockErrorType::FileReadError(config.files.alias.clone()),
-                traceback: e.to_string(),
-            })?,
-            Err(e) if e.kind() == std::io::ErrorKind::NotFound => Default::default(),
-            Err(e) => Err(SherlockError {
-                error: SherlockErrorType::FileReadError(config.files.alias.clone()),
-                traceback: e.to_string(),
-            })?,
-        };
+        let alias_rules = AliasRules::read(&config.files.alias)?;
 
         // Gather '.desktop' files
-        let desktop_files: HashSet<PathBuf> = match applications {
+        let desktop_files: HashMap<String, PathBuf> = match applications {
             Some(apps) => apps,
             _ => get_desktop_files(system_apps),
         };
@@ -74,67 +59,121 @@ impl Loader {
         // Parellize opening of all .desktop files and parsing them into AppData
         let apps: HashMap<String, AppData> = desktop_files
             .into_par_iter()
-            .filter_map(|entry| {
+            .filter_map(|(id, entry)| {
                 let r_path = entry.to_str()?;
                 match read_file(r_path) {
                     Ok(content) => {
-                        if parse_field(&content, &display_re) == "true" {
+                        let file = DesktopFile::parse(&content);
+                        let group = file.entry()?;
+                        if group.string("Type").is_some_and(|t| t != "Application")
+                            || group.boolean("Hidden")
+                            || group.boolean("NoDisplay")
+                            || !group.shown_in(&desktops)
+                            || !group.try_exec_found()
+                        {
                             return None;
                         }
 
                         // Extract keywords, icon, and name fields
-                        let mut keywords = parse_field(&content, &keywords_re);
-                        let mut icon = parse_field(&content, &icon_re);
-                        let mut name = parse_field(&content, &name_re);
-                        if name.is_empty() || should_ignore(&ignore_apps, &name) {
+                        let keywords = group.locale_list("Keywords", &locale).join(";");
+                        let icon = group.string("Icon").unwrap_or_default();
+                        let name = group.locale_string("Name", &locale).unwrap_or_default();
+                        let untranslated_name =
+                            group.string("Name").filter(|n| !n.is_empty() && *n != name);
+                        if name.is_empty() {
                             return None; // Skip entries with empty names
                         }
 
                         // Construct the executable command
-                        let exec_path = parse_field(&content, &exec_re);
-                        let mut exec = if parse_field(&content, &terminal_re) == "true" {
-                            format!("{} {}", &config.default_apps.terminal, exec_path)
-                        } else {
-                            exec_path.to_string()
-                        };
-
-                        // apply aliases
-                        if let Some(alias) = aliases.get(&name) {
-                            if let Some(alias_name) = alias.name.as_ref() {
-                                name = alias_name.to_string();
-                            }
-                            if let Some(alias_icon) = alias.icon.as_ref() {
-                                icon = alias_icon.to_string();
-                            }
-                            if let Some(alias_keywords) = alias.keywords.as_ref() {
-                                keywords = alias_keywords.to_string();
-                            }
-                            if let Some(alias_exec) = alias.exec.as_ref() {
-                                exec = alias_exec.to_string();
-                            }
-                        };
-                        let search_string = format!("{};{}", name, keywords);
+                        let exec_path = group.string("Exec").unwrap_or_default();
+                        if exec_path.is_empty() {
+                            return None;
+                        }
 
-                        let desktop_file_path = match config.behavior.caching {
-                            true => Some(entry),
-                            false => None,
+                        // Apply the rules of 'sherlockignore'
+                        let categories = group.list("Categories");
+                        let names: Vec<&str> = std::iter::once(name.as_str())
+                            .chain(untranslated_name.as_deref())
+                            .collect();
+                        let target = IgnoreTarget {
+                            names: &names,
+                            id: Some(&id),
+                            categories: &categories,
+                            exec: &exec_path,
                         };
+                        if ignore_rules.is_ignored(&target) {
+                            return None;
+                        }
 
-                        // apply counts
-                        let count = counts.get(&exec).unwrap_or(&0.0);
-                        let priority = parse_priority(priority, *count, decimals);
+                        // Apply the most specific aliases for the ID and names
+                        let alias = alias_rules.resolve(&id, &names).unwrap_or_default();
+                        if alias.hidden == Some(true) {
+                            return None;
+                        }
+                        // The terminal is only put in front once the exec is expanded
+                        let terminal = alias.terminal.unwrap_or(group.boolean("Terminal"));
+                        let exec = alias.exec.unwrap_or(exec_path);
+
+                        // Collect the actions listed in 'Actions'
+                        let actions: Vec<AppAction> = group
+                            .list("Actions")
+                            .iter()
+                            .filter_map(|id| {
+                                let action = file.group(&format!("Desktop Action {}", id))?;
+                                let name = action
+                                    .locale_string("Name", &locale)
+                                    .filter(|n| !n.is_empty())?;
+                                let exec = action.string("Exec").filter(|e| !e.is_empty())?;
+                                let f = frecency.get(&exec).copied().unwrap_or(0.0);
+                                Some(AppAction {
+                                    name,
+                                    exec,
+                                    icon: action.string("Icon").filter(|i| !i.is_empty()),
+                                    frecency: frecency_score(f),
+                                })
+                            })
+                            .collect();
+
+                        let generic_name = group
+                            .locale_string("GenericName", &locale)
+                            .filter(|s| !s.is_empty());
+                        let comment = group
+                            .locale_string("Comment", &locale)
+                            .filter(|s| !s.is_empty());
+                        let cwd = alias
+                            .cwd
+                            .or_else(|| group.string("Path").filter(|s| !s.is_empty()));
+
+                        // apply frecency
+                        let f = frecency.get(&exec).copied().unwrap_or(0.0);
 
                         // Return the processed app data
                         Some((
-                            name,
+                            id,
                             AppData {
-                                icon,
+                                name: Some(alias.name.unwrap_or(name)),
+                                icon: alias.icon.unwrap_or(icon),
                                 exec,
-                                search_string,
+                                keywords: alias.keywords.unwrap_or(keywords),
+                                untranslated_name,
+                                generic_name,
+                                comment,
+                                categories,
+                                cwd,
+                                actions,
                                 tag_start: None,
                                 tag_end: None,
-                                desktop_file: desktop_file_path,
+                                desktop_file: Some(entry),
+                                env: alias.env,
+                                launch_prefix: alias.launch_prefix,
+                                boost: alias.boost.unwrap_or(0.0),
+                                shell: false,
+                                terminal,
+                                hold: false,
+                                callbacks: Default::default(),
+                                args: Vec::new(),
                                 priority,
+                                frecency: frecency_score(f),
                             },
                         ))
                     }
@@ -145,186 +184,284 @@ impl Loader {
         Ok(apps)
     }
 
+    /// Brings `apps` up to date with the desktop files on disk. Only the files
+    /// in `stale` are read, besides the ones of apps that were dropped because
+    /// another file with their ID takes precedence now. Without `stale`, every
+    /// file that is not in `apps` is read.
     fn get_new_applications(
         mut apps: HashMap<String, AppData>,
+        stale: Option<&HashSet<PathBuf>>,
         priority: f32,
-        counts: HashMap<String, f32>,
-        decimals: i32,
+        frecency: &HashMap<String, f32>,
     ) -> Result<HashMap<String, AppData>, SherlockError> {
         let system_apps = get_applications_dir();
 
         // get all desktop files
         let mut desktop_files = get_desktop_files(system_apps);
 
-        // remove if cached entry doesnt exist on device anympre
-        let mut cached_paths = HashSet::with_capacity(apps.capacity());
-        apps.retain(|_, v| {
-            if let Some(path) = &v.desktop_file {
-                if desktop_files.contains(path) {
-                    cached_paths.insert(path.clone());
-                    return true;
-                }
+        // remove if cached entry doesnt exist on device anympre, or if another
+        // file with the same ID takes precedence now
+        let mut dropped: HashSet<String> = HashSet::new();
+        apps.retain(|id, v| {
+            let current =
+                v.desktop_file.is_some() && desktop_files.get(id) == v.desktop_file.as_ref();
+            if !current {
+                dropped.insert(id.clone());
             }
-            false
+            current
         });
 
-        // get files that are not yet cached
-        desktop_files.retain(|v| {
-            return !cached_paths.contains(v);
+        // get files that are not yet cached. Hidden and ignored apps are never
+        // cached, so they are only read again once their file changed.
+        desktop_files.retain(|id, path| {
+            !apps.contains_key(id)
+                && stale.is_none_or(|stale| stale.contains(path) || dropped.contains(id))
         });
 
         // get information for uncached applications
-        match Loader::load_applications_from_disk(Some(desktop_files), priority, counts, decimals) {
+        match Loader::load_applications_from_disk(Some(desktop_files), priority, frecency) {
             Ok(new_apps) => apps.extend(new_apps),
             _ => {}
         };
         return Ok(apps);
     }
 
-    fn write_cache<T: AsRef<Path>>(apps: &HashMap<String, AppData>, cache_loc: T) {
-        let tmp_path = cache_loc.as_ref().with_extension(".tmp");
-        if let Ok(f) = File::create(&tmp_path) {
-            if let Ok(_) = simd_json::to_writer(f, &apps) {
-                let _ = fs::rename(&tmp_path, &cache_loc);
-            } else {
-                let _ = fs::remove_file(&tmp_path);
+    pub fn load_applications(
+        priority: f32,
+        frecency: &HashMap<String, f32>,
+    ) -> Result<AppIndex, SherlockError> {
+        let config = CONFIG.get().ok_or_else(|| SherlockError {
+            error: SherlockErrorType::ConfigError(None),
+            traceback: String::new(),
+        })?;
+        let files = stamp_desktop_files();
+        let config_hash = cache_config_hash();
+
+        // Only desktop files that changed since the cache was written are read.
+        // Caches written with other settings are discarded completely.
+        let cache = AppCache::read(&config.behavior.cache)
+            .filter(|cache| cache.config_hash == config_hash)
+            .unwrap_or_default();
+        let mut apps = cache.apps;
+        apps.retain(|id, _| {
+            files
+                .get(id)
+                .is_some_and(|s| cache.files.get(id) == Some(s))
+        });
+        let stale: HashMap<String, PathBuf> = files
+            .iter()
+            .filter(|(id, stamp)| cache.files.get(*id) != Some(stamp))
+            .map(|(id, stamp)| (id.clone(), stamp.path.clone()))
+            .collect();
+        if !stale.is_empty() {
+            apps.extend(Loader::load_applications_from_disk(
+                Some(stale),
+                priority,
+                frecency,
+            )?);
+        }
+
+        // apply the current frecency
+        for (_, v) in apps.iter_mut() {
+            let f = frecency.get(&v.exec).copied().unwrap_or(0.0);
+            v.priority = priority;
+            v.frecency = frecency_score(f);
+            for action in v.actions.iter_mut() {
+                let f = frecency.get(&action.exec).copied().unwrap_or(0.0);
+                action.frecency = frecency_score(f);
             }
         }
+
+        // Write the cache in the background
+        if cache.files != files {
+            let cache = AppCache {
+                config_hash,
+                files,
+                apps: apps.clone(),
+            };
+            rayon::spawn_fifo(move || {
+                let _ = cache.write(&config.behavior.cache);
+            });
+        }
+        Ok(Arc::new(RwLock::new(apps)))
     }
 
-    pub fn load_applications(
-        priority: f32,
-        counts: HashMap<String, f32>,
-        decimals: i32,
-    ) -> Result<HashMap<String, AppData>, SherlockError> {
+    /// Registers the apps of an app launcher to be updated by
+    /// [`Loader::refresh_app_indexes`]
+    pub fn register_app_index(index: &AppIndex, priority: f32) {
+        if let Ok(mut indexes) = APP_INDEXES.lock() {
+            indexes.push((Arc::downgrade(index), priority));
+        }
+    }
+
+    /// Updates the apps of every running app launcher. Only the desktop files
+    /// in `changed`, which includes added ones, are read again. Removed ones
+    /// are detected on their own. If `full` is set, e.g. because the alias or
+    /// ignore file changed, every desktop file is read again.
+    pub fn refresh_app_indexes(
+        changed: &HashSet<PathBuf>,
+        full: bool,
+    ) -> Result<(), SherlockError> {
         let config = CONFIG.get().ok_or_else(|| SherlockError {
             error: SherlockErrorType::ConfigError(None),
             traceback: String::new(),
         })?;
-        // check if sherlock_alias was modified
-        let alias_path = Path::new(&config.files.alias);
-        let ignore_path = Path::new(&config.files.ignore);
-        let cache_path = Path::new(&config.behavior.cache);
-        let changed = file_has_changed(&alias_path, &cache_path)
-            || file_has_changed(&ignore_path, &cache_path);
-
-        if !changed {
-            let cached_apps: Option<HashMap<String, AppData>> = File::open(&config.behavior.cache)
-                .ok()
-                .and_then(|f| simd_json::from_reader(f).ok());
-
-            if let Some(mut apps) = cached_apps {
-                // apply the current counts
-                for (_, v) in apps.iter_mut() {
-                    let count = counts.get(&v.exec).unwrap_or(&0.0);
-                    let priority = parse_priority(priority, *count, decimals);
-                    v.priority = priority
-                }
+        let frecency = CounterReader::new()?.read_frecency()?;
+        // Taken before reading, so files that change meanwhile count as stale
+        let files = stamp_desktop_files();
+
+        let indexes: Vec<(AppIndex, f32)> = match APP_INDEXES.lock() {
+            Ok(mut indexes) => {
+                indexes.retain(|(index, _)| index.strong_count() > 0);
+                indexes
+                    .iter()
+                    .filter_map(|(index, priority)| Some((index.upgrade()?, *priority)))
+                    .collect()
+            }
+            Err(_) => return Ok(()),
+        };
 
-                // Refresh cache in the background
-                let old_apps = apps.clone();
-                rayon::spawn_fifo(move || {
-                    if let Ok(new_apps) =
-                        Loader::get_new_applications(old_apps, priority, counts, decimals)
-                    {
-                        Loader::write_cache(&new_apps, &config.behavior.cache);
-                    }
+        let mut refreshed = None;
+        for (index, priority) in indexes {
+            let (mut current, stale) = match full {
+                true => (HashMap::new(), None),
+                false => (
+                    index.read().map(|apps| apps.clone()).unwrap_or_default(),
+                    Some(changed),
+                ),
+            };
+            current.retain(|_, v| v.desktop_file.as_ref().is_none_or(|p| !changed.contains(p)));
+            let apps = Loader::get_new_applications(current, stale, priority, &frecency)?;
+            if let Ok(mut index) = index.write() {
+                *index = apps.clone();
+            }
+            refreshed = Some(apps);
+        }
+        if let Some(apps) = refreshed.filter(|_| config.behavior.caching) {
+            let cache = AppCache {
+                config_hash: cache_config_hash(),
+                files,
+                apps,
+            };
+            if let Err(e) = cache.write(&config.behavior.cache) {
+                return Err(SherlockError {
+                    error: SherlockErrorType::FileWriteError(config.behavior.cache.clone()),
+                    traceback: e.to_string(),
                 });
-                return Ok(apps);
             }
         }
-
-        let apps = Loader::load_applications_from_disk(None, priority, counts, decimals)?;
-        // Write the cache in the background
-        let app_clone = apps.clone();
-        rayon::spawn_fifo(move || Loader::write_cache(&app_clone, &config.behavior.cache));
-        Ok(apps)
+        Ok(())
     }
 }
 
-fn should_ignore(ignore_apps: &Vec<Pattern>, app: &str) -> bool {
-    let app_name = app.to_lowercase();
-    ignore_apps.iter().any(|pattern| pattern.matches(&app_name))
+/// Stamps of all desktop files, keyed by their desktop file ID
+fn stamp_desktop_files() -> HashMap<String, FileStamp> {
+    get_desktop_files(get_applications_dir())
+        .into_par_iter()
+        .map(|(id, path)| (id, FileStamp::new(&path)))
+        .collect()
 }
 
-fn get_regex_patterns() -> Result<(Regex, Regex, Regex, Regex, Regex, Regex), SherlockError> {
-    fn construct_pattern(key: &str) -> Result<Regex, SherlockError> {
-        let pattern = format!(r"(?i)\n{}\s*=\s*(.*)\n", key);
-        Regex::new(&pattern).map_err(|e| SherlockError {
-            error: SherlockErrorType::RegexError(key.to_string()),
-            traceback: e.to_string(),
-        })
-    }
-    let name = construct_pattern("Name")?;
-    let icon = construct_pattern("Icon")?;
-    let exec = construct_pattern("Exec")?;
-    let display = construct_pattern("NoDisplay")?;
-    let terminal = construct_pattern("Terminal")?;
-    let keywords = construct_pattern("Keywords")?;
-    return Ok((name, icon, exec, display, terminal, keywords));
+/// Hash of the settings the cached apps depend on. The alias and ignore files
+/// apply to every app, so a change to either of them invalidates the cache.
+fn cache_config_hash() -> u64 {
+    let stamp = |path: &Path| mtime(path).unwrap_or(0).to_string();
+    let (launch_prefix, alias, ignore) = match CONFIG.get() {
+        Some(c) => (
+            c.behavior.launch_prefix.clone().unwrap_or_default(),
+            stamp(&c.files.alias),
+            stamp(&c.files.ignore),
+        ),
+        None => Default::default(),
+    };
+    config_hash(&[
+        &Locale::current().to_string(),
+        &launch_prefix,
+        &current_desktops().join(":"),
+        &alias,
+        &ignore,
+    ])
 }
 
-pub fn get_applications_dir() -> HashSet<PathBuf> {
-    let xdg_paths = match env::var("XDG_DATA_DIRS").ok() {
-        Some(paths) => {
-            let app_dirs: HashSet<PathBuf> = paths
-                .split(":")
-                .map(|p| PathBuf::from(p).join("applications/"))
-                .collect();
-            app_dirs
-        }
-        _ => HashSet::new(),
-    };
+/// Returns the directories that contain desktop files, in order of precedence:
+/// `$XDG_DATA_HOME` first, then every directory of `$XDG_DATA_DIRS`
+pub fn get_applications_dir() -> Vec<PathBuf> {
     let home = env::var("HOME").ok().unwrap_or("~".to_string());
-    let mut default_paths = vec![
-        String::from("/usr/share/applications/"),
-        String::from("~/.local/share/applications/"),
-    ];
+    let data_home = env::var("XDG_DATA_HOME")
+        .ok()
+        .filter(|p| !p.is_empty())
+        .unwrap_or(format!("{}/.local/share", home));
+    let data_dirs = env::var("XDG_DATA_DIRS")
+        .ok()
+        .filter(|p| !p.is_empty())
+        .unwrap_or(String::from("/usr/local/share:/usr/share"));
+
+    let mut paths: Vec<PathBuf> = std::iter::once(data_home.as_str())
+        .chain(data_dirs.split(':').filter(|p| !p.is_empty()))
+        .map(|p| PathBuf::from(p).join("applications/"))
+        .collect();
     if let Some(c) = CONFIG.get() {
-        default_paths.extend(c.debug.app_paths.clone());
+        paths.extend(
+            c.debug
+                .app_paths
+                .iter()
+                .map(|path| PathBuf::from(path.replace("~", &home))),
+        );
     };
 
-    let mut paths: HashSet<PathBuf> = default_paths
-        .iter()
-        .map(|path| path.replace("~", &home))
-        .map(|path| PathBuf::from(path))
-        .collect();
-    paths.extend(xdg_paths);
+    // Keep the first occurrence of each directory
+    let mut seen = HashSet::new();
+    paths.retain(|p| seen.insert(p.clone()));
     paths
 }
 
-pub fn get_desktop_files(dirs: HashSet<PathBuf>) -> HashSet<PathBuf> {
-    dirs.into_par_iter()
-        .filter_map(|dir| {
-            fs::read_dir(dir).ok().map(|entries| {
-                entries
-                    .filter_map(|entry| {
-                        entry.ok().and_then(|f| {
-                            let path = f.path();
-                            if path.extension().and_then(|ext| ext.to_str()) == Some("desktop") {
-                                Some(path)
-                            } else {
-                                None
-                            }
-                        })
-                    })
-                    .collect::<Vec<PathBuf>>()
-            })
+/// Collects the desktop files of all directories, keyed by their desktop file
+/// ID. If several directories contain the same ID, the first one wins.
+pub fn get_desktop_files(dirs: Vec<PathBuf>) -> HashMap<String, PathBuf> {
+    let found: Vec<Vec<(String, PathBuf)>> = dirs
+        .into_par_iter()
+        .map(|dir| {
+            let mut files = Vec::new();
+            collect_desktop_files(&dir, &dir, &mut files);
+            files
         })
-        .flatten()
-        .collect::<HashSet<PathBuf>>()
-}
-pub fn file_has_changed(file_path: &Path, cache_path: &Path) -> bool {
-    fn modtime(path: &Path) -> Option<SystemTime> {
-        fs::metadata(path).ok().and_then(|m| m.modified().ok())
+        .collect();
+
+    let mut desktop_files = HashMap::new();
+    for (id, path) in found.into_iter().flatten() {
+        desktop_files.entry(id).or_insert(path);
     }
-    match (modtime(&file_path), modtime(&cache_path)) {
-        (Some(t1), Some(t2)) if t1 >= t2 => return true,
-        _ => {}
+    desktop_files
+}
+
+fn collect_desktop_files(root: &Path, dir: &Path, files: &mut Vec<(String, PathBuf)>) {
+    let entries = match fs::read_dir(dir) {
+        Ok(entries) => entries,
+        Err(_) => return,
+    };
+    for entry in entries.filter_map(Result::ok) {
+        let path = entry.path();
+        if path.is_dir() {
+            collect_desktop_files(root, &path, files);
+        } else if path.extension().and_then(|ext| ext.to_str()) == Some("desktop") {
+            if let Some(id) = desktop_file_id(root, &path) {
+                files.push((id, path));
+            }
+        }
     }
-    return false;
 }
 
+/// The ID of a desktop file is its path relative to the applications directory
+/// with `/` replaced by `-`, e.g. `kde/konsole.desktop` becomes
+/// `kde-konsole.desktop`.
+fn desktop_file_id(root: &Path, path: &Path) -> Option<String> {
+    let relative = path.strip_prefix(root).ok()?;
+    let parts: Vec<&str> = relative
+        .components()
+        .map(|c| c.as_os_str().to_str())
+        .collect::<Option<_>>()?;
+    Some(parts.join("-"))
+}
 #[test]
 fn test_get_applications_dir() {
     // Test input path
@@ -354,3 +491,24 @@ fn test_get_applications_dir() {
     // Assert that the result matches the expected HashSet
     assert_eq!(res, expected_app_dirs);
 }
+
+#[test]
+fn test_desktop_file_id() {
+    let root = Path::new("/usr/share/applications/");
+    assert_eq!(
+        desktop_file_id(root, Path::new("/usr/share/applications/firefox.desktop")).as_deref(),
+        Some("firefox.desktop")
+    );
+    assert_eq!(
+        desktop_file_id(
+            root,
+            Path::new("/usr/share/applications/kde/konsole.desktop")
+        )
+        .as_deref(),
+        Some("kde-konsole.desktop")
+    );
+    assert_eq!(
+        desktop_file_id(root, Path::new("/opt/other/app.desktop")),
+        None
+    );
+}
@@ -161,6 +161,9 @@ impl Loader {
         if sherlock_flags.daemonize {
             config.behavior.daemonize = true;
         }
+        if sherlock_flags.explain_ranking {
+            config.ranking.explain = true;
+        }
         config
     }
 }
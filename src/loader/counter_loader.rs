@@ -1,3 +1,147 @@
+use std::collections::{HashMap, HashSet};
+use std::fs::{self, File};
+use std::path::PathBuf;
+use std::time::{SystemTime, UNIX_EPOCH};
 
+use super::util::{home_dir, tmp_path, SherlockError, SherlockErrorType};
 
+/// Number of launch timestamps kept per exec
+const MAX_LAUNCHES: usize = 20;
+/// Time in seconds after which a launch only counts half as much
+const HALF_LIFE: f32 = 7.0 * 24.0 * 60.0 * 60.0;
 
+pub struct CounterReader {
+    path: PathBuf,
+    legacy_path: PathBuf,
+}
+impl CounterReader {
+    pub fn new() -> Result<Self, SherlockError> {
+        let home = home_dir()?;
+        let path = home.join(".sherlock/frecency.json");
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent).map_err(|e| SherlockError {
+                error: SherlockErrorType::DirCreateError(".sherlock".to_string()),
+                traceback: e.to_string(),
+            })?;
+        }
+        Ok(CounterReader {
+            path,
+            legacy_path: home.join(".sherlock/counts.json"),
+        })
+    }
+    pub fn write(&self, launches: &HashMap<String, Vec<u64>>) -> Result<(), SherlockError> {
+        let tmp_path = tmp_path(&self.path);
+        let f = File::create(&tmp_path).map_err(|e| SherlockError {
+            error: SherlockErrorType::FileWriteError(tmp_path.clone()),
+            traceback: e.to_string(),
+        })?;
+        match simd_json::to_writer(f, launches) {
+            Ok(_) => fs::rename(&tmp_path, &self.path).map_err(|e| SherlockError {
+                error: SherlockErrorType::FileWriteError(self.path.clone()),
+                traceback: e.to_string(),
+            }),
+            Err(e) => {
+                let _ = fs::remove_file(&tmp_path);
+                Err(SherlockError {
+                    error: SherlockErrorType::FileWriteError(self.path.clone()),
+                    traceback: e.to_string(),
+                })
+            }
+        }
+    }
+    /// Reads the launch timestamps of every exec
+    pub fn read(&self) -> Result<HashMap<String, Vec<u64>>, SherlockError> {
+        match File::open(&self.path) {
+            Ok(f) => Ok(simd_json::from_reader(f).unwrap_or_default()),
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(self.migrate_legacy()),
+            Err(e) => Err(SherlockError {
+                error: SherlockErrorType::FileReadError(self.path.clone()),
+                traceback: e.to_string(),
+            }),
+        }
+    }
+    /// Reads the launch history and condenses it into one frecency value per exec
+    pub fn read_frecency(&self) -> Result<HashMap<String, f32>, SherlockError> {
+        let now = now();
+        let frecency = self
+            .read()?
+            .into_iter()
+            .map(|(exec, launches)| (exec, frecency(&launches, now)))
+            .filter(|(_, f)| *f > 0.0)
+            .collect();
+        Ok(frecency)
+    }
+    pub fn increment(&self, key: &str) -> Result<(), SherlockError> {
+        let mut content = self.read()?;
+        let launches = content.entry(key.to_string()).or_default();
+        launches.push(now());
+        if launches.len() > MAX_LAUNCHES {
+            let excess = launches.len() - MAX_LAUNCHES;
+            launches.drain(..excess);
+        }
+        self.write(&content)
+    }
+    /// Removes the history of execs that are not provided by any launcher anymore,
+    /// e.g. after an app was uninstalled or its exec was changed in the alias file.
+    pub fn prune(&self, execs: &HashSet<String>) -> Result<(), SherlockError> {
+        let mut content = self.read()?;
+        let len = content.len();
+        content.retain(|exec, _| execs.contains(exec));
+        if content.len() != len || !self.path.exists() {
+            self.write(&content)?;
+        }
+        Ok(())
+    }
+    fn migrate_legacy(&self) -> HashMap<String, Vec<u64>> {
+        // Older versions stored a plain execution count per exec. Those counts
+        // are imported as launches at the time the legacy file was last written.
+        let modified = fs::metadata(&self.legacy_path)
+            .and_then(|m| m.modified())
+            .ok()
+            .and_then(|t| t.duration_since(UNIX_EPOCH).ok())
+            .map_or_else(now, |d| d.as_secs());
+        let counts: HashMap<String, f32> = File::open(&self.legacy_path)
+            .ok()
+            .and_then(|f| simd_json::from_reader(f).ok())
+            .unwrap_or_default();
+        counts
+            .into_iter()
+            .filter(|(_, count)| *count >= 1.0)
+            .map(|(exec, count)| {
+                let n = (count as usize).min(MAX_LAUNCHES);
+                (exec, vec![modified; n])
+            })
+            .collect()
+    }
+}
+
+pub fn now() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map_or(0, |d| d.as_secs())
+}
+
+/// Sums up all launches, each weighted by an exponential decay of its age
+pub fn frecency(launches: &[u64], now: u64) -> f32 {
+    launches
+        .iter()
+        .map(|t| {
+            let age = now.saturating_sub(*t) as f32;
+            0.5f32.powf(age / HALF_LIFE)
+        })
+        .sum()
+}
+
+#[test]
+fn test_frecency() {
+    let now = 100 * HALF_LIFE as u64;
+    let day = 24 * 60 * 60;
+    assert_eq!(frecency(&[], now), 0.0);
+    assert_eq!(frecency(&[now], now), 1.0);
+    assert!((frecency(&[now - HALF_LIFE as u64], now) - 0.5).abs() < 1e-6);
+
+    // Two recent launches outweigh five launches from two months ago
+    let recent = frecency(&[now - day, now - 2 * day], now);
+    let old = frecency(&[now - 60 * day; 5], now);
+    assert!(recent > old);
+}
@@ -0,0 +1,467 @@
+use std::collections::HashMap;
+use std::env;
+use std::fs;
+use std::os::unix::fs::PermissionsExt;
+use std::path::Path;
+
+/// Name of the group that describes the application itself
+pub const MAIN_GROUP: &str = "Desktop Entry";
+
+/// A `.desktop` file parsed according to the freedesktop Desktop Entry
+/// specification. Keys are stored per group, so keys of `[Desktop Action]`
+/// groups never leak into the main entry. Localized keys like `Name[de]` are
+/// kept apart from their unlocalized key.
+#[derive(Debug, Default)]
+pub struct DesktopFile {
+    groups: Vec<DesktopGroup>,
+}
+impl DesktopFile {
+    pub fn parse(content: &str) -> Self {
+        let mut groups: Vec<DesktopGroup> = Vec::new();
+        let mut current: Option<DesktopGroup> = None;
+        for line in content.lines() {
+            let line = line.trim();
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+            if let Some(name) = line.strip_prefix('[').and_then(|l| l.strip_suffix(']')) {
+                groups.extend(current.take());
+                // Group names have to be unique, only the first one counts
+                if !groups.iter().any(|g| g.name == name) {
+                    current = Some(DesktopGroup {
+                        name: name.to_string(),
+                        entries: HashMap::new(),
+                    });
+                }
+                continue;
+            }
+            // Entries outside of a (valid) group are ignored
+            let group = match current.as_mut() {
+                Some(group) => group,
+                None => continue,
+            };
+            if let Some((key, value)) = line.split_once('=') {
+                group
+                    .entries
+                    .entry(key.trim_end().to_string())
+                    .or_insert_with(|| value.trim_start().to_string());
+            }
+        }
+        groups.extend(current);
+        DesktopFile { groups }
+    }
+    pub fn group(&self, name: &str) -> Option<&DesktopGroup> {
+        self.groups.iter().find(|g| g.name == name)
+    }
+    /// The `[Desktop Entry]` group
+    pub fn entry(&self) -> Option<&DesktopGroup> {
+        self.group(MAIN_GROUP)
+    }
+}
+
+#[derive(Debug, Default)]
+pub struct DesktopGroup {
+    pub name: String,
+    entries: HashMap<String, String>,
+}
+impl DesktopGroup {
+    /// Value of `key` with its escape sequences resolved
+    pub fn string(&self, key: &str) -> Option<String> {
+        self.entries.get(key).map(|v| unescape(v))
+    }
+    pub fn boolean(&self, key: &str) -> bool {
+        self.entries.get(key).is_some_and(|v| v == "true")
+    }
+    /// Values of a `;` separated list. Empty values are skipped.
+    pub fn list(&self, key: &str) -> Vec<String> {
+        self.entries
+            .get(key)
+            .map(|v| split_list(v))
+            .unwrap_or_default()
+    }
+    /// Like [`string`](Self::string), but prefers the translation that best
+    /// matches `locale`, e.g. `Name[de_DE]` or `Name[de]`.
+    pub fn locale_string(&self, key: &str, locale: &Locale) -> Option<String> {
+        self.localized(key, locale).map(|v| unescape(v))
+    }
+    /// Like [`list`](Self::list), but prefers the translation that best
+    /// matches `locale`
+    pub fn locale_list(&self, key: &str, locale: &Locale) -> Vec<String> {
+        self.localized(key, locale)
+            .map(|v| split_list(v))
+            .unwrap_or_default()
+    }
+    fn localized(&self, key: &str, locale: &Locale) -> Option<&String> {
+        locale
+            .variants()
+            .iter()
+            .find_map(|l| self.entries.get(&format!("{}[{}]", key, l)))
+            .or_else(|| self.entries.get(key))
+    }
+    /// Checks `OnlyShowIn` and `NotShowIn` against the names of the current
+    /// desktop environments, as found in `XDG_CURRENT_DESKTOP`.
+    pub fn shown_in(&self, desktops: &[String]) -> bool {
+        let matches = |list: Vec<String>| {
+            list.iter()
+                .any(|d| desktops.iter().any(|c| c.eq_ignore_ascii_case(d)))
+        };
+        let only = self.list("OnlyShowIn");
+        if !only.is_empty() && !matches(only) {
+            return false;
+        }
+        !matches(self.list("NotShowIn"))
+    }
+    /// Whether the program named in `TryExec` is installed. Entries without
+    /// `TryExec` are always considered installed.
+    pub fn try_exec_found(&self) -> bool {
+        match self.string("TryExec") {
+            Some(program) if !program.is_empty() => find_executable(&program),
+            _ => true,
+        }
+    }
+}
+
+/// The locale used to pick translated keys, in the form
+/// `lang_COUNTRY@MODIFIER` with the country and modifier being optional
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct Locale {
+    lang: String,
+    country: Option<String>,
+    modifier: Option<String>,
+}
+impl Locale {
+    /// Reads the locale from `LC_ALL`, `LC_MESSAGES` or `LANG`, in that order.
+    /// The `C` and `POSIX` locales have no translations.
+    pub fn current() -> Self {
+        ["LC_ALL", "LC_MESSAGES", "LANG"]
+            .iter()
+            .filter_map(|var| env::var(var).ok())
+            .find(|value| !value.is_empty())
+            .map(|value| Locale::parse(&value))
+            .unwrap_or_default()
+    }
+    pub fn parse(value: &str) -> Self {
+        // The encoding, as in `de_DE.UTF-8@euro`, is not part of the key
+        let (rest, modifier) = match value.split_once('@') {
+            Some((rest, modifier)) => (rest, Some(modifier.to_string())),
+            None => (value, None),
+        };
+        let rest = rest.split('.').next().unwrap_or_default();
+        let (lang, country) = match rest.split_once('_') {
+            Some((lang, country)) => (lang, Some(country.to_string())),
+            None => (rest, None),
+        };
+        if lang.is_empty() || lang == "C" || lang == "POSIX" {
+            return Locale::default();
+        }
+        Locale {
+            lang: lang.to_string(),
+            country,
+            modifier,
+        }
+    }
+    /// The locale keys to look for, from the most to the least specific one
+    pub fn variants(&self) -> Vec<String> {
+        if self.lang.is_empty() {
+            return Vec::new();
+        }
+        let mut variants = Vec::with_capacity(4);
+        if let (Some(country), Some(modifier)) = (&self.country, &self.modifier) {
+            variants.push(format!("{}_{}@{}", self.lang, country, modifier));
+        }
+        if let Some(country) = &self.country {
+            variants.push(format!("{}_{}", self.lang, country));
+        }
+        if let Some(modifier) = &self.modifier {
+            variants.push(format!("{}@{}", self.lang, modifier));
+        }
+        variants.push(self.lang.clone());
+        variants
+    }
+}
+impl std::fmt::Display for Locale {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self.variants().first() {
+            Some(key) => write!(f, "{}", key),
+            None => write!(f, "C"),
+        }
+    }
+}
+
+/// Values for the field codes of an `Exec` key. Sherlock never passes files or
+/// URLs, so `%f`, `%F`, `%u` and `%U` are always removed.
+#[derive(Debug, Default)]
+pub struct FieldCodes<'a> {
+    /// Translated name of the app, for `%c`
+    pub name: Option<&'a str>,
+    /// Icon of the app, for `%i`
+    pub icon: Option<&'a str>,
+    /// Location of the desktop file, for `%k`
+    pub desktop_file: Option<&'a str>,
+}
+
+/// Splits an `Exec` value into the program and its arguments and expands its
+/// field codes. Arguments are separated by spaces and may be quoted with `"`,
+/// in which a backslash escapes `"`, `` ` ``, `$` and itself. Quoted text is
+/// taken literally, `%%` stands for a literal `%` and unknown field codes are
+/// kept as they are. Returns `None` if a quote is not closed.
+pub fn expand_exec(exec: &str, codes: &FieldCodes) -> Option<Vec<String>> {
+    parse_exec(exec, Some(codes))
+}
+
+/// Splits a command line like [`expand_exec`] does, without expanding field
+/// codes, e.g. for a launch prefix
+pub fn split_exec(exec: &str) -> Option<Vec<String>> {
+    parse_exec(exec, None)
+}
+
+fn parse_exec(exec: &str, codes: Option<&FieldCodes>) -> Option<Vec<String>> {
+    let mut args: Vec<String> = Vec::new();
+    let mut arg = String::new();
+    // Empty quotes ("") are an argument of their own
+    let mut quoted = false;
+    let mut chars = exec.chars().peekable();
+    while let Some(c) = chars.next() {
+        match c {
+            c if c.is_whitespace() => {
+                if !arg.is_empty() || quoted {
+                    args.push(std::mem::take(&mut arg));
+                }
+                quoted = false;
+            }
+            '"' => {
+                quoted = true;
+                loop {
+                    match chars.next()? {
+                        '"' => break,
+                        '\\' => match chars.next()? {
+                            e @ ('"' | '`' | '$' | '\\') => arg.push(e),
+                            other => {
+                                arg.push('\\');
+                                arg.push(other);
+                            }
+                        },
+                        other => arg.push(other),
+                    }
+                }
+            }
+            // Not allowed by the spec, but common enough to be accepted
+            '\\' => arg.push(chars.next().unwrap_or('\\')),
+            '%' => {
+                let codes = match codes {
+                    Some(codes) => codes,
+                    None => {
+                        arg.push('%');
+                        continue;
+                    }
+                };
+                match chars.peek().copied() {
+                    Some('%') => arg.push('%'),
+                    Some('f' | 'F' | 'u' | 'U' | 'd' | 'D' | 'n' | 'N' | 'v' | 'm') => {}
+                    Some('c') => arg.push_str(codes.name.unwrap_or_default()),
+                    Some('k') => arg.push_str(codes.desktop_file.unwrap_or_default()),
+                    // Only valid as an argument of its own, dropped otherwise
+                    Some('i') => {
+                        let mut rest = chars.clone();
+                        rest.next();
+                        let standalone = arg.is_empty()
+                            && !quoted
+                            && rest.peek().is_none_or(|c| c.is_whitespace());
+                        if let Some(icon) = codes.icon.filter(|i| standalone && !i.is_empty()) {
+                            args.push(String::from("--icon"));
+                            args.push(icon.to_string());
+                        }
+                    }
+                    _ => {
+                        arg.push('%');
+                        continue;
+                    }
+                }
+                chars.next();
+            }
+            c => arg.push(c),
+        }
+    }
+    if !arg.is_empty() || quoted {
+        args.push(arg);
+    }
+    Some(args)
+}
+
+/// Names of the current desktop environments, e.g. `["GNOME"]`
+pub fn current_desktops() -> Vec<String> {
+    env::var("XDG_CURRENT_DESKTOP")
+        .map(|d| {
+            d.split(':')
+                .filter(|s| !s.is_empty())
+                .map(String::from)
+                .collect()
+        })
+        .unwrap_or_default()
+}
+
+fn find_executable(program: &str) -> bool {
+    fn is_executable(path: &Path) -> bool {
+        fs::metadata(path).is_ok_and(|m| m.is_file() && m.permissions().mode() & 0o111 != 0)
+    }
+    if program.contains('/') {
+        return is_executable(Path::new(program));
+    }
+    env::var_os("PATH")
+        .is_some_and(|paths| env::split_paths(&paths).any(|dir| is_executable(&dir.join(program))))
+}
+
+/// Resolves the escape sequences `\s`, `\n`, `\t`, `\r` and `\\`. Unknown
+/// sequences are kept as they are.
+fn unescape(value: &str) -> String {
+    let mut out = String::with_capacity(value.len());
+    let mut chars = value.chars();
+    while let Some(c) = chars.next() {
+        if c != '\\' {
+            out.push(c);
+            continue;
+        }
+        match chars.next() {
+            Some('s') => out.push(' '),
+            Some('n') => out.push('\n'),
+            Some('t') => out.push('\t'),
+            Some('r') => out.push('\r'),
+            Some('\\') => out.push('\\'),
+            Some(other) => {
+                out.push('\\');
+                out.push(other);
+            }
+            None => out.push('\\'),
+        }
+    }
+    out
+}
+
+/// Splits a list value on every `;` that is not escaped as `\;`
+fn split_list(value: &str) -> Vec<String> {
+    let mut items = Vec::new();
+    let mut item = String::new();
+    let mut chars = value.chars();
+    while let Some(c) = chars.next() {
+        match c {
+            '\\' => match chars.next() {
+                Some(';') => item.push(';'),
+                Some(next) => {
+                    item.push('\\');
+                    item.push(next);
+                }
+                None => item.push('\\'),
+            },
+            ';' => items.push(std::mem::take(&mut item)),
+            c => item.push(c),
+        }
+    }
+    items.push(item);
+    items
+        .iter()
+        .map(|i| unescape(i))
+        .filter(|i| !i.is_empty())
+        .collect()
+}
+
+#[test]
+fn test_desktop_file() {
+    let content = "# Comment\n\
+        [Desktop Entry]\n\
+        Type=Application\n\
+        Name = Text Editor\n\
+        Name[de]=Texteditor\n\
+        Name=Duplicate\n\
+        Comment=Edit\\stext\\nfiles\\\\\n\
+        Keywords=text;editor;semi\\;colon;;\n\
+        Categories=Utility;TextEditor\n\
+        NoDisplay=false\n\
+        \n\
+        [Desktop Action new-window]\n\
+        Name=New Window\n\
+        Exec=editor --new-window\n\
+        [Desktop Entry]\n\
+        Exec=ignored\n\
+        Icon=editor";
+    let file = DesktopFile::parse(content);
+    let entry = file.entry().expect("main group should exist");
+    assert_eq!(entry.string("Name").as_deref(), Some("Text Editor"));
+    assert_eq!(entry.string("Name[de]").as_deref(), Some("Texteditor"));
+    assert_eq!(
+        entry.string("Comment").as_deref(),
+        Some("Edit text\nfiles\\")
+    );
+    assert_eq!(entry.list("Keywords"), vec!["text", "editor", "semi;colon"]);
+    assert_eq!(entry.list("Categories"), vec!["Utility", "TextEditor"]);
+    assert!(!entry.boolean("NoDisplay"));
+
+    // Keys of other groups and of repeated groups do not leak into the entry
+    assert_eq!(entry.string("Exec"), None);
+    assert_eq!(entry.string("Icon"), None);
+    let action = file.group("Desktop Action new-window").unwrap();
+    assert_eq!(
+        action.string("Exec").as_deref(),
+        Some("editor --new-window")
+    );
+
+    let locale = Locale::parse("de_AT.UTF-8@euro");
+    assert_eq!(
+        locale.variants(),
+        vec!["de_AT@euro", "de_AT", "de@euro", "de"]
+    );
+    assert_eq!(
+        entry.locale_string("Name", &locale).as_deref(),
+        Some("Texteditor")
+    );
+    let locale = Locale::parse("fr_FR.UTF-8");
+    assert_eq!(
+        entry.locale_string("Name", &locale).as_deref(),
+        Some("Text Editor")
+    );
+    assert!(Locale::parse("C.UTF-8").variants().is_empty());
+
+    let desktops = vec![String::from("GNOME")];
+    let shown = DesktopFile::parse("[Desktop Entry]\nOnlyShowIn=KDE;GNOME;");
+    assert!(shown.entry().unwrap().shown_in(&desktops));
+    let hidden = DesktopFile::parse("[Desktop Entry]\nNotShowIn=GNOME;");
+    assert!(!hidden.entry().unwrap().shown_in(&desktops));
+}
+
+#[test]
+fn test_expand_exec() {
+    let codes = FieldCodes {
+        name: Some("Text Editor"),
+        icon: Some("editor"),
+        desktop_file: Some("/usr/share/applications/editor.desktop"),
+    };
+    let expand = |exec: &str| expand_exec(exec, &codes).unwrap();
+    assert_eq!(expand("editor --new %U"), vec!["editor", "--new"]);
+    assert_eq!(
+        expand(r#""/opt/My App/bin" "say \"hi\"" a\ b"#),
+        vec!["/opt/My App/bin", "say \"hi\"", "a b"]
+    );
+    assert_eq!(
+        expand("editor %i --class=%c -f %k"),
+        vec![
+            "editor",
+            "--icon",
+            "editor",
+            "--class=Text Editor",
+            "-f",
+            "/usr/share/applications/editor.desktop"
+        ]
+    );
+    assert_eq!(expand("editor %ifoo a%i"), vec!["editor", "foo", "a"]);
+    // Literal percent signs survive
+    assert_eq!(
+        expand(r#"date +%s 100%% "%f""#),
+        vec!["date", "+%s", "100%", "%f"]
+    );
+    assert_eq!(expand(r#"echo """#), vec!["echo", ""]);
+    assert_eq!(expand_exec(r#"echo "open"#, &codes), None);
+
+    assert_eq!(
+        split_exec(r#"uwsm app -- "%f""#).unwrap(),
+        vec!["uwsm", "app", "--", "%f"]
+    );
+}
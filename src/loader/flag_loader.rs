@@ -3,6 +3,7 @@ use std::{env, path::PathBuf};
 use crate::actions::util::eval_exit;
 
 use super::{
+    learning_loader::QueryLearner,
     util::{SherlockError, SherlockFlags},
     Loader,
 };
@@ -22,6 +23,15 @@ impl Loader {
             let _ = print_version();
             eval_exit();
         }
+        if args.contains(&"--forget".to_string()) {
+            if let Err(e) = QueryLearner::new().and_then(|learner| learner.forget()) {
+                let (name, message) = e.error.get_message();
+                eprintln!("{}: {}\n{}", name, message, e.traceback);
+            } else {
+                println!("Forgot all learned queries.");
+            }
+            eval_exit();
+        }
 
         SherlockFlags::new(args)
     }
@@ -59,6 +69,7 @@ impl SherlockFlags {
             daemonize: check_flag_existance("--daemonize"),
             method: extract_flag_value("--method"),
             field: extract_flag_value("--field"),
+            explain_ranking: check_flag_existance("--explain-ranking"),
         })
     }
 }
@@ -92,6 +103,14 @@ pub fn print_help() -> Result<(), SherlockError> {
             "--method",
             "For pipe mode only: Specifies what to do with the selected data row",
         ),
+        (
+            "--explain-ranking",
+            "Show the score breakdown of each result as its tooltip.",
+        ),
+        (
+            "--forget",
+            "Clear the learned queries used to rank previously chosen results.",
+        ),
     ];
 
     // Print header
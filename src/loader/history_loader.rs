@@ -0,0 +1,94 @@
+use serde::{Deserialize, Serialize};
+use std::fs::{self, File};
+use std::path::PathBuf;
+
+use super::counter_loader::now;
+use super::util::{home_dir, tmp_path, SherlockError, SherlockErrorType};
+
+/// Number of queries kept in the history
+const MAX_ENTRIES: usize = 200;
+
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub struct HistoryEntry {
+    pub query: String,
+    /// Alias of the mode the query was run in, `all` if none was active
+    pub mode: String,
+    pub time: u64,
+}
+
+/// Persists the queries that led to a launched result, newest first
+pub struct HistoryReader {
+    path: PathBuf,
+}
+impl HistoryReader {
+    pub fn new() -> Result<Self, SherlockError> {
+        let home = home_dir()?;
+        let path = home.join(".sherlock/history.json");
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent).map_err(|e| SherlockError {
+                error: SherlockErrorType::DirCreateError(".sherlock".to_string()),
+                traceback: e.to_string(),
+            })?;
+        }
+        Ok(HistoryReader { path })
+    }
+    pub fn read(&self) -> Result<Vec<HistoryEntry>, SherlockError> {
+        match File::open(&self.path) {
+            Ok(f) => Ok(simd_json::from_reader(f).unwrap_or_default()),
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(Vec::new()),
+            Err(e) => Err(SherlockError {
+                error: SherlockErrorType::FileReadError(self.path.clone()),
+                traceback: e.to_string(),
+            }),
+        }
+    }
+    pub fn write(&self, entries: &[HistoryEntry]) -> Result<(), SherlockError> {
+        let tmp_path = tmp_path(&self.path);
+        let f = File::create(&tmp_path).map_err(|e| SherlockError {
+            error: SherlockErrorType::FileWriteError(tmp_path.clone()),
+            traceback: e.to_string(),
+        })?;
+        match simd_json::to_writer(f, entries) {
+            Ok(_) => fs::rename(&tmp_path, &self.path).map_err(|e| SherlockError {
+                error: SherlockErrorType::FileWriteError(self.path.clone()),
+                traceback: e.to_string(),
+            }),
+            Err(e) => {
+                let _ = fs::remove_file(&tmp_path);
+                Err(SherlockError {
+                    error: SherlockErrorType::FileWriteError(self.path.clone()),
+                    traceback: e.to_string(),
+                })
+            }
+        }
+    }
+    /// Moves the query to the top of the history. An earlier occurrence of the
+    /// same query in the same mode is removed.
+    pub fn push(&self, query: &str, mode: &str) -> Result<(), SherlockError> {
+        let query = query.trim();
+        if query.is_empty() {
+            return Ok(());
+        }
+        let mut entries = self.read()?;
+        entries.retain(|e| e.query != query || e.mode != mode);
+        entries.insert(
+            0,
+            HistoryEntry {
+                query: query.to_string(),
+                mode: mode.to_string(),
+                time: now(),
+            },
+        );
+        entries.truncate(MAX_ENTRIES);
+        self.write(&entries)
+    }
+    /// Returns the queries that were run in `mode`, newest first
+    pub fn queries(&self, mode: &str) -> Result<Vec<String>, SherlockError> {
+        Ok(self
+            .read()?
+            .into_iter()
+            .filter(|e| e.mode == mode)
+            .map(|e| e.query)
+            .collect())
+    }
+}
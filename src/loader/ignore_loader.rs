@@ -0,0 +1,205 @@
+use glob::{MatchOptions, Pattern};
+use regex::{Regex, RegexBuilder};
+use std::fs;
+use std::path::Path;
+
+use super::util::{SherlockError, SherlockErrorType};
+
+/// The parsed `sherlockignore` file. Every line is one rule of the form
+/// `[!][field:]pattern`:
+///
+/// - `field` is one of `name` (the default), `id`, `category` or `exec`
+/// - `pattern` is a glob, or a regex if it is wrapped in slashes (`/^qt/`)
+/// - `category:` patterns may list several categories separated by `;`, which
+///   all have to be present
+/// - `!` re-includes entries that were ignored by an earlier rule
+///
+/// All patterns are case insensitive. Like in a `.gitignore`, the last rule
+/// that matches an entry decides.
+#[derive(Debug, Default)]
+pub struct IgnoreRules {
+    rules: Vec<IgnoreRule>,
+}
+
+/// An entry to check against the ignore rules
+#[derive(Debug, Default)]
+pub struct IgnoreTarget<'a> {
+    /// Display names of the entry, e.g. its translated and untranslated name
+    pub names: &'a [&'a str],
+    /// The desktop file ID, for apps
+    pub id: Option<&'a str>,
+    pub categories: &'a [String],
+    pub exec: &'a str,
+}
+
+#[derive(Debug)]
+struct IgnoreRule {
+    field: Field,
+    /// One matcher per category for `category:` rules
+    matchers: Vec<Matcher>,
+    negated: bool,
+}
+
+#[derive(Debug, PartialEq)]
+enum Field {
+    Name,
+    Id,
+    Category,
+    Exec,
+}
+
+#[derive(Debug)]
+enum Matcher {
+    Glob(Pattern),
+    Regex(Regex),
+}
+impl Matcher {
+    fn new(pattern: &str) -> Option<Self> {
+        match pattern
+            .strip_prefix('/')
+            .and_then(|p| p.strip_suffix('/'))
+            .filter(|p| !p.is_empty())
+        {
+            Some(regex) => RegexBuilder::new(regex)
+                .case_insensitive(true)
+                .build()
+                .ok()
+                .map(Matcher::Regex),
+            None => Pattern::new(pattern).ok().map(Matcher::Glob),
+        }
+    }
+    fn matches(&self, text: &str) -> bool {
+        match self {
+            Matcher::Glob(pattern) => pattern.matches_with(
+                text,
+                MatchOptions {
+                    case_sensitive: false,
+                    ..Default::default()
+                },
+            ),
+            Matcher::Regex(regex) => regex.is_match(text),
+        }
+    }
+}
+
+impl IgnoreRules {
+    /// Reads the rules from `path`. A missing file means that nothing is
+    /// ignored.
+    pub fn read(path: &Path) -> Result<Self, SherlockError> {
+        match fs::read_to_string(path) {
+            Ok(content) => Ok(IgnoreRules::parse(&content)),
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(IgnoreRules::default()),
+            Err(e) => Err(SherlockError {
+                error: SherlockErrorType::FileReadError(path.to_path_buf()),
+                traceback: e.to_string(),
+            }),
+        }
+    }
+    /// Parses the rules. Lines that are empty, start with `#` or hold an
+    /// invalid pattern are skipped.
+    pub fn parse(content: &str) -> Self {
+        let rules = content
+            .lines()
+            .map(str::trim)
+            .filter(|line| !line.is_empty() && !line.starts_with('#'))
+            .filter_map(|line| {
+                let (negated, line) = match line.strip_prefix('!') {
+                    Some(rest) => (true, rest.trim_start()),
+                    None => (false, line),
+                };
+                let (field, pattern) = match line.split_once(':') {
+                    Some(("name", pattern)) => (Field::Name, pattern),
+                    Some(("id", pattern)) => (Field::Id, pattern),
+                    Some(("category", pattern)) => (Field::Category, pattern),
+                    Some(("exec", pattern)) => (Field::Exec, pattern),
+                    _ => (Field::Name, line),
+                };
+                let pattern = pattern.trim();
+                let matchers = match field {
+                    Field::Category => pattern
+                        .split(';')
+                        .filter(|c| !c.is_empty())
+                        .map(Matcher::new)
+                        .collect::<Option<Vec<_>>>()?,
+                    _ => vec![Matcher::new(pattern)?],
+                };
+                (!matchers.is_empty()).then_some(IgnoreRule {
+                    field,
+                    matchers,
+                    negated,
+                })
+            })
+            .collect();
+        IgnoreRules { rules }
+    }
+    pub fn is_ignored(&self, target: &IgnoreTarget) -> bool {
+        self.rules
+            .iter()
+            .rev()
+            .find(|rule| rule.matches(target))
+            .is_some_and(|rule| !rule.negated)
+    }
+}
+
+impl IgnoreRule {
+    fn matches(&self, target: &IgnoreTarget) -> bool {
+        match self.field {
+            Field::Name => target.names.iter().any(|n| self.matchers[0].matches(n)),
+            Field::Id => target.id.is_some_and(|id| self.matchers[0].matches(id)),
+            Field::Exec => self.matchers[0].matches(target.exec),
+            Field::Category => self
+                .matchers
+                .iter()
+                .all(|m| target.categories.iter().any(|c| m.matches(c))),
+        }
+    }
+}
+
+#[test]
+fn test_ignore_rules() {
+    let rules = IgnoreRules::parse(
+        "# comment\n\
+         Avahi*\n\
+         id:org.kde.*\n\
+         !id:org.kde.dolphin.desktop\n\
+         category:Settings;X-XFCE\n\
+         exec:/opt/*\n\
+         /^qt\\d/\n",
+    );
+    let settings = [String::from("Settings"), String::from("X-XFCE")];
+    let ignored = |name: &str, id: &str, categories: &[String], exec: &str| {
+        rules.is_ignored(&IgnoreTarget {
+            names: &[name],
+            id: Some(id),
+            categories,
+            exec,
+        })
+    };
+
+    assert!(ignored("Avahi Browser", "avahi.desktop", &[], "avahi"));
+    assert!(ignored(
+        "Konsole",
+        "org.kde.konsole.desktop",
+        &[],
+        "konsole"
+    ));
+    assert!(!ignored(
+        "Dolphin",
+        "org.kde.dolphin.desktop",
+        &[],
+        "dolphin"
+    ));
+    assert!(ignored("Panel", "panel.desktop", &settings, "panel"));
+    assert!(!ignored("Panel", "panel.desktop", &settings[..1], "panel"));
+    assert!(ignored("App", "app.desktop", &[], "/opt/app/bin"));
+    assert!(ignored("Qt5 Settings", "qt5ct.desktop", &[], "qt5ct"));
+    assert!(!ignored("Firefox", "firefox.desktop", &[], "firefox"));
+
+    // Commands have neither an ID nor categories
+    let command = IgnoreTarget {
+        names: &["avahi discover"],
+        exec: "avahi-discover",
+        ..Default::default()
+    };
+    assert!(rules.is_ignored(&command));
+}
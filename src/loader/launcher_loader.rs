@@ -3,22 +3,23 @@ use rayon::iter::ParallelIterator;
 use serde_json::Value;
 use std::collections::{HashMap, HashSet};
 
-use std::env;
-use std::fs::{self, File};
+use std::fs::File;
 use std::path::PathBuf;
+use std::sync::{Arc, RwLock};
 
 use crate::actions::util::read_from_clipboard;
 use crate::launcher::audio_launcher::AudioLauncherFunctions;
 use crate::launcher::calc_launcher::Calculator;
-use crate::launcher::category_launcher::CategoryLauncher;
+use crate::launcher::category_launcher::{AppCategoryLauncher, CategoryLauncher};
 use crate::launcher::event_launcher::EventLauncher;
+use crate::launcher::history_launcher::HistoryLauncher;
 use crate::launcher::process_launcher::ProcessLauncher;
 use crate::launcher::{
     app_launcher, bulk_text_launcher, clipboard_launcher, system_cmd_launcher, web_launcher,
     Launcher, LauncherType,
 };
 
-use app_launcher::App;
+use app_launcher::{App, AppIndex};
 use bulk_text_launcher::BulkText;
 use clipboard_launcher::ClipboardLauncher;
 use simd_json;
@@ -26,7 +27,10 @@ use simd_json::prelude::ArrayTrait;
 use system_cmd_launcher::SystemCommand;
 use web_launcher::Web;
 
-use super::util::parse_priority;
+use super::counter_loader::CounterReader;
+use super::ignore_loader::{IgnoreRules, IgnoreTarget};
+use super::learning_loader::QueryLearner;
+use super::util::frecency_score;
 use super::{
     util::{self, SherlockError, SherlockErrorType},
     Loader,
@@ -50,56 +54,53 @@ fn wrapped() -> Result<(Vec<Launcher>, Vec<SherlockError>), SherlockError> {
     })?;
 
     // Read fallback data here:
-    let (launcher_config, n) = parse_launcher_configs(&config.files.fallback)?;
+    let (launcher_config, mut n) = parse_launcher_configs(&config.files.fallback)?;
 
-    // Read cached counter file
+    // Read the 'sherlockignore' rules, which also apply to commands and categories
+    let ignore_rules = IgnoreRules::read(&config.files.ignore)
+        .map_err(|e| n.push(e))
+        .unwrap_or_default();
+
+    // Read launch history and condense it into frecency values
     let counter_reader = CounterReader::new()?;
-    let counts = counter_reader.read()?;
+    let frecency = counter_reader.read_frecency()?;
+
+    // The app launcher and the app categories share one index, so the apps are
+    // only read, cached and watched once. Its priority is the app launcher's.
+    let app_config = launcher_config
+        .iter()
+        .find(|cmd| cmd.r#type == "app_launcher")
+        .or_else(|| {
+            launcher_config
+                .iter()
+                .find(|cmd| cmd.r#type == "app_categories")
+        });
+    let app_index = app_config.map(|cmd| load_app_index(cmd.priority, &frecency));
 
     // Parse the launchers
     let deserialized_launchers: Vec<Result<Launcher, SherlockError>> = launcher_config
         .into_par_iter()
         .map(|cmd| {
-            let counts_clone = counts.clone();
-            let max_decimals = counts_clone
-                .iter()
-                .max_by(|a, b| a.1.partial_cmp(&b.1).unwrap_or(std::cmp::Ordering::Equal))
-                .map(|(_, v)| v.to_string().len())
-                .unwrap_or(0) as i32;
             let launcher_type: LauncherType = match cmd.r#type.as_str() {
                 "categories" => {
                     let prio = cmd.priority;
                     let mut categories: HashMap<String, AppData> =
                         serde_json::from_value(cmd.args["categories"].clone()).unwrap_or_default();
+                    apply_ignore_rules(&mut categories, &ignore_rules);
                     categories.iter_mut().for_each(|(_, v)| {
-                        v.priority = match counts_clone.get(&v.exec) {
-                            Some(c) if c == &0.0 => prio,
-                            Some(c) => parse_priority(prio, *c as f32, max_decimals),
-                            _ => prio,
-                        };
+                        let f = frecency.get(&v.exec).copied().unwrap_or(0.0);
+                        v.priority = prio;
+                        v.frecency = frecency_score(f);
                     });
                     LauncherType::CategoryLauncher(CategoryLauncher { categories })
                 }
-                "app_launcher" => {
-                    let mut apps: HashMap<String, AppData> = HashMap::new();
-                    if let Some(c) = CONFIG.get() {
-                        apps = match c.behavior.caching {
-                            true => Loader::load_applications(
-                                cmd.priority as f32,
-                                counts_clone,
-                                max_decimals,
-                            )?,
-                            false => Loader::load_applications_from_disk(
-                                None,
-                                cmd.priority as f32,
-                                counts_clone,
-                                max_decimals,
-                            )?,
-                        };
-                    }
-
-                    LauncherType::App(App { apps })
-                }
+                "app_launcher" => LauncherType::App(App {
+                    apps: shared_app_index(&app_index)?,
+                }),
+                "app_categories" => LauncherType::AppCategories(AppCategoryLauncher {
+                    apps: shared_app_index(&app_index)?,
+                    priority: cmd.priority,
+                }),
                 "web_launcher" => LauncherType::Web(Web {
                     display_name: cmd.display_name.clone().unwrap_or("".to_string()),
                     icon: cmd.args["icon"].as_str().unwrap_or_default().to_string(),
@@ -125,12 +126,11 @@ fn wrapped() -> Result<(Vec<Launcher>, Vec<SherlockError>), SherlockError> {
                     let prio = cmd.priority;
                     let mut commands: HashMap<String, AppData> =
                         serde_json::from_value(cmd.args["commands"].clone()).unwrap_or_default();
+                    apply_ignore_rules(&mut commands, &ignore_rules);
                     commands.iter_mut().for_each(|(_, v)| {
-                        v.priority = match counts_clone.get(&v.exec) {
-                            Some(c) if c == &0.0 => prio,
-                            Some(c) => parse_priority(prio, *c as f32, max_decimals),
-                            _ => prio,
-                        };
+                        let f = frecency.get(&v.exec).copied().unwrap_or(0.0);
+                        v.priority = prio;
+                        v.frecency = frecency_score(f);
                     });
                     LauncherType::SystemCommand(SystemCommand { commands })
                 }
@@ -194,8 +194,19 @@ fn wrapped() -> Result<(Vec<Launcher>, Vec<SherlockError>), SherlockError> {
                         LauncherType::Empty
                     }
                 }
+                "history" => LauncherType::History(HistoryLauncher {
+                    icon: cmd.args["icon"]
+                        .as_str()
+                        .unwrap_or("document-open-recent")
+                        .to_string(),
+                }),
                 _ => LauncherType::Empty,
             };
+            // Typo tolerance is enabled for apps and opt-in for everything else
+            let typo_tolerance = cmd.typo_tolerance.unwrap_or(matches!(
+                cmd.r#type.as_str(),
+                "app_launcher" | "app_categories"
+            ));
             let method: String = if let Some(value) = &cmd.on_return {
                 value.to_string()
             } else {
@@ -215,6 +226,7 @@ fn wrapped() -> Result<(Vec<Launcher>, Vec<SherlockError>), SherlockError> {
                 launcher_type,
                 shortcut: cmd.shortcut,
                 spawn_focus: cmd.spawn_focus,
+                typo_tolerance,
             })
         })
         .collect();
@@ -223,13 +235,19 @@ fn wrapped() -> Result<(Vec<Launcher>, Vec<SherlockError>), SherlockError> {
     let (oks, errs): (Vec<_>, Vec<_>) = deserialized_launchers.into_iter().partition(Result::is_ok);
     let launchers: Vec<Launcher> = oks.into_iter().filter_map(Result::ok).collect();
     let mut non_breaking: Vec<SherlockError> = errs.into_iter().filter_map(Result::err).collect();
-    if counts.is_empty() {
-        let counts: HashMap<String, f32> = launchers
+
+    // Drop the launch history of execs that no longer exist. Skipped if a launcher
+    // failed to load, to not lose the history of its entries.
+    if non_breaking.is_empty() {
+        let execs: HashSet<String> = launchers
             .iter()
             .filter_map(|launcher| launcher.get_execs())
-            .flat_map(|exec_set| exec_set.into_iter().map(|exec| (exec, 0.0)))
+            .flatten()
             .collect();
-        if let Err(e) = counter_reader.write(counts) {
+        if let Err(e) = counter_reader.prune(&execs) {
+            non_breaking.push(e)
+        };
+        if let Err(e) = QueryLearner::new().and_then(|learner| learner.prune(&execs)) {
             non_breaking.push(e)
         };
     }
@@ -237,63 +255,51 @@ fn wrapped() -> Result<(Vec<Launcher>, Vec<SherlockError>), SherlockError> {
     Ok((launchers, non_breaking))
 }
 
-pub struct CounterReader {
-    path: PathBuf,
+/// Loads the installed apps and registers them to be kept up to date by the app
+/// watcher
+fn load_app_index(
+    priority: f32,
+    frecency: &HashMap<String, f32>,
+) -> Result<AppIndex, SherlockError> {
+    let config = CONFIG.get().ok_or_else(|| SherlockError {
+        error: SherlockErrorType::ConfigError(None),
+        traceback: String::new(),
+    })?;
+    let apps: AppIndex = match config.behavior.caching {
+        true => Loader::load_applications(priority, frecency)?,
+        false => Arc::new(RwLock::new(Loader::load_applications_from_disk(
+            None, priority, frecency,
+        )?)),
+    };
+    Loader::register_app_index(&apps, priority);
+    Ok(apps)
 }
-impl CounterReader {
-    pub fn new() -> Result<Self, SherlockError> {
-        let home = env::var("HOME").map_err(|e| SherlockError {
-            error: SherlockErrorType::EnvVarNotFoundError("HOME".to_string()),
-            traceback: e.to_string(),
-        })?;
-        let home_dir = PathBuf::from(home);
-        let path = home_dir.join(".sherlock/counts.json");
-        if let Some(parent) = path.parent() {
-            fs::create_dir_all(parent).map_err(|e| SherlockError {
-                error: SherlockErrorType::DirCreateError(".sherlock".to_string()),
-                traceback: e.to_string(),
-            })?;
-        }
-        Ok(CounterReader { path })
-    }
-    pub fn write(&self, counts: HashMap<String, f32>) -> Result<(), SherlockError> {
-        let tmp_path = self.path.with_extension(".tmp");
-        if let Ok(f) = File::create(&tmp_path) {
-            if let Ok(_) = simd_json::to_writer(f, &counts) {
-                let _ = fs::rename(&tmp_path, &self.path);
-            } else {
-                let _ = fs::remove_file(&tmp_path);
-            }
-        }
-        Ok(())
-    }
-    pub fn read(&self) -> Result<HashMap<String, f32>, SherlockError> {
-        let file = if self.path.exists() {
-            File::open(&self.path)
-        } else {
-            File::create(&self.path)
-        }
-        .map_err(|e| SherlockError {
-            error: SherlockErrorType::FileExistError(self.path.clone()),
-            traceback: e.to_string(),
-        })?;
-        let counts = match simd_json::from_reader(file).ok() {
-            Some(c) => c,
-            _ => HashMap::new(),
-        };
 
-        Ok(counts)
-    }
-    pub fn increment(&self, key: &str) -> Result<(), SherlockError> {
-        let mut content = self.read()?;
-        if let Some(value) = content.get_mut(key) {
-            *value += 1.0;
-        };
-        self.write(content)?;
-        Ok(())
+/// The app index loaded for the app launchers
+fn shared_app_index(
+    index: &Option<Result<AppIndex, SherlockError>>,
+) -> Result<AppIndex, SherlockError> {
+    match index {
+        Some(index) => index.clone(),
+        None => Err(SherlockError {
+            error: SherlockErrorType::ConfigError(None),
+            traceback: String::from("The app index was not loaded"),
+        }),
     }
 }
 
+/// Drops the entries that are ignored by the 'sherlockignore' rules
+fn apply_ignore_rules(entries: &mut HashMap<String, AppData>, rules: &IgnoreRules) {
+    entries.retain(|name, v| {
+        !rules.is_ignored(&IgnoreTarget {
+            names: &[name],
+            categories: &v.categories,
+            exec: &v.exec,
+            ..Default::default()
+        })
+    });
+}
+
 fn parse_launcher_configs(
     fallback_path: &PathBuf,
 ) -> Result<(Vec<CommandConfig>, Vec<SherlockError>), SherlockError> {
@@ -0,0 +1,172 @@
+use rusqlite::{params, Connection};
+use std::cell::RefCell;
+use std::collections::{HashMap, HashSet};
+use std::fs;
+use std::path::PathBuf;
+use std::rc::Rc;
+
+use super::counter_loader::now;
+use super::util::{home_dir, SherlockError, SherlockErrorType};
+
+/// Time in seconds after which a learned pick only counts half as much
+const HALF_LIFE: f32 = 14.0 * 24.0 * 60.0 * 60.0;
+/// Picks that were not repeated within this many seconds are forgotten
+const MAX_AGE: u64 = 90 * 24 * 60 * 60;
+/// Upper bound for the number of stored (query, exec) pairs
+const MAX_ROWS: u32 = 2000;
+
+thread_local! {
+    /// The learner of this thread, see [`QueryLearner::shared`]
+    static SHARED: RefCell<Option<Rc<QueryLearner>>> = const { RefCell::new(None) };
+}
+
+/// Remembers which exec was chosen for which query, so that the same query
+/// (or a prefix of it) ranks that result higher the next time.
+pub struct QueryLearner {
+    path: PathBuf,
+    conn: Connection,
+}
+impl QueryLearner {
+    pub fn new() -> Result<Self, SherlockError> {
+        let home = home_dir()?;
+        let path = home.join(".sherlock/learned_queries.db");
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent).map_err(|e| SherlockError {
+                error: SherlockErrorType::DirCreateError(".sherlock".to_string()),
+                traceback: e.to_string(),
+            })?;
+        }
+        let conn = Connection::open(&path).map_err(|e| SherlockError {
+            error: SherlockErrorType::DatabaseError(path.clone()),
+            traceback: e.to_string(),
+        })?;
+        let learner = QueryLearner { path, conn };
+        learner.execute(
+            "CREATE TABLE IF NOT EXISTS picks (
+                query       TEXT NOT NULL,
+                exec        TEXT NOT NULL,
+                count       INTEGER NOT NULL,
+                last_used   INTEGER NOT NULL,
+                PRIMARY KEY (query, exec)
+            )",
+            [],
+        )?;
+        Ok(learner)
+    }
+    /// Opens the database on first use and reuses the connection afterwards,
+    /// so that looking up boosts on every keystroke doesn't reopen it
+    pub fn shared() -> Result<Rc<Self>, SherlockError> {
+        SHARED.with(|shared| {
+            let mut shared = shared.borrow_mut();
+            if let Some(learner) = shared.as_ref() {
+                return Ok(Rc::clone(learner));
+            }
+            let learner = Rc::new(QueryLearner::new()?);
+            *shared = Some(Rc::clone(&learner));
+            Ok(learner)
+        })
+    }
+    /// Records that `exec` was chosen while `query` was typed into the search bar
+    pub fn learn(&self, query: &str, exec: &str) -> Result<(), SherlockError> {
+        let query = normalize(query);
+        if query.is_empty() || exec.is_empty() {
+            return Ok(());
+        }
+        self.execute(
+            "INSERT INTO picks (query, exec, count, last_used) VALUES (?1, ?2, 1, ?3)
+            ON CONFLICT (query, exec) DO UPDATE SET count = count + 1, last_used = ?3",
+            params![query, exec, now() as i64],
+        )
+    }
+    /// Returns a boost in `0.0..1.0` for every exec that was previously picked for
+    /// a query starting with `query`. Picks for the exact query and recent picks
+    /// weigh more.
+    pub fn boosts(&self, query: &str) -> Result<HashMap<String, f32>, SherlockError> {
+        let query = normalize(query);
+        let mut boosts: HashMap<String, f32> = HashMap::new();
+        if query.is_empty() {
+            return Ok(boosts);
+        }
+        let map_err = |e: rusqlite::Error| SherlockError {
+            error: SherlockErrorType::DatabaseError(self.path.clone()),
+            traceback: e.to_string(),
+        };
+        let mut stmt = self
+            .conn
+            .prepare(
+                "SELECT query, exec, count, last_used FROM picks
+                WHERE substr(query, 1, length(?1)) = ?1",
+            )
+            .map_err(map_err)?;
+        let rows = stmt
+            .query_map(params![query], |row| {
+                Ok((
+                    row.get::<_, String>(0)?,
+                    row.get::<_, String>(1)?,
+                    row.get::<_, i64>(2)?,
+                    row.get::<_, i64>(3)?,
+                ))
+            })
+            .map_err(map_err)?;
+
+        let now = now();
+        let query_len = query.chars().count() as f32;
+        for (learned_query, exec, count, last_used) in rows.flatten() {
+            let age = now.saturating_sub(last_used as u64) as f32;
+            let closeness = query_len / learned_query.chars().count() as f32;
+            let weight = count as f32 * closeness * 0.5f32.powf(age / HALF_LIFE);
+            *boosts.entry(exec).or_default() += weight;
+        }
+        boosts.values_mut().for_each(|w| *w = *w / (*w + 1.0));
+        Ok(boosts)
+    }
+    /// Forgets old picks and picks of execs that no longer exist
+    pub fn prune(&self, execs: &HashSet<String>) -> Result<(), SherlockError> {
+        let cutoff = now().saturating_sub(MAX_AGE) as i64;
+        self.execute("DELETE FROM picks WHERE last_used < ?1", params![cutoff])?;
+        self.execute(
+            "DELETE FROM picks WHERE rowid NOT IN (
+                SELECT rowid FROM picks ORDER BY last_used DESC LIMIT ?1
+            )",
+            params![MAX_ROWS],
+        )?;
+
+        let stale: Vec<String> = {
+            let mut stmt = self
+                .conn
+                .prepare("SELECT DISTINCT exec FROM picks")
+                .map_err(|e| SherlockError {
+                    error: SherlockErrorType::DatabaseError(self.path.clone()),
+                    traceback: e.to_string(),
+                })?;
+            let rows = stmt
+                .query_map([], |row| row.get::<_, String>(0))
+                .map_err(|e| SherlockError {
+                    error: SherlockErrorType::DatabaseError(self.path.clone()),
+                    traceback: e.to_string(),
+                })?;
+            rows.flatten().filter(|e| !execs.contains(e)).collect()
+        };
+        for exec in stale {
+            self.execute("DELETE FROM picks WHERE exec = ?1", params![exec])?;
+        }
+        Ok(())
+    }
+    /// Removes every learned pick
+    pub fn forget(&self) -> Result<(), SherlockError> {
+        self.execute("DELETE FROM picks", [])
+    }
+    fn execute<P: rusqlite::Params>(&self, sql: &str, params: P) -> Result<(), SherlockError> {
+        self.conn
+            .execute(sql, params)
+            .map(|_| ())
+            .map_err(|e| SherlockError {
+                error: SherlockErrorType::DatabaseError(self.path.clone()),
+                traceback: e.to_string(),
+            })
+    }
+}
+
+fn normalize(query: &str) -> String {
+    query.trim().to_lowercase()
+}
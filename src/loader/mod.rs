@@ -1,9 +1,16 @@
+pub mod alias_loader;
+pub mod app_cache;
 pub mod application_loader;
 pub mod config_loader;
+pub mod counter_loader;
 pub mod css_loader;
+pub mod desktop_entry;
 pub mod flag_loader;
+pub mod history_loader;
 pub mod icon_loader;
+pub mod ignore_loader;
 pub mod launcher_loader;
+pub mod learning_loader;
 pub mod pipe_loader;
 pub mod resource_loader;
 pub mod util;
@@ -25,8 +25,10 @@ impl Loader {
     }
 }
 
-pub fn deserialize_pipe(mut buf: Vec<u8>) -> Vec<PipeData> {
-    let data: Option<Vec<PipeData>> = simd_json::from_slice(&mut buf).ok();
+pub fn deserialize_pipe(buf: Vec<u8>) -> Vec<PipeData> {
+    // simd_json parses in place, so a copy keeps the input intact for the
+    // plain-line fallback
+    let data: Option<Vec<PipeData>> = simd_json::from_slice(&mut buf.clone()).ok();
 
     let config = match CONFIG.get() {
         Some(c) => c,
@@ -39,27 +41,13 @@ pub fn deserialize_pipe(mut buf: Vec<u8>) -> Vec<PipeData> {
                 if i.field.is_none() {
                     i.field = config.behavior.field.clone();
                 }
-                if let Some(title) = &i.title {
-                    let cleaned: String = title
-                        .chars()
-                        .filter(|&c| c.is_ascii() && (!c.is_control() || c == '\t' || c == '\n'))
-                        .collect();
-                    i.title = Some(cleaned);
-                }
-                if let Some(desc) = &i.description {
-                    let cleaned: String = desc
-                        .chars()
-                        .filter(|&c| c.is_ascii() && (!c.is_control() || c == '\t' || c == '\n'))
-                        .collect();
-                    i.description = Some(cleaned);
-                }
-                if let Some(res) = &i.result {
-                    let cleaned: String = res
-                        .chars()
-                        .filter(|&c| c.is_ascii() && (!c.is_control() || c == '\t' || c == '\n'))
-                        .collect();
-                    i.result = Some(cleaned);
+                // Only the displayed fields are cleaned, the result is printed
+                // exactly as it was piped in.
+                if i.result.is_none() {
+                    i.result = i.title.clone();
                 }
+                i.title = i.title.as_deref().map(sanitize);
+                i.description = i.description.as_deref().map(sanitize);
             }
             parsed_data
         }
@@ -78,17 +66,24 @@ pub fn deserialize_pipe(mut buf: Vec<u8>) -> Vec<PipeData> {
 
                 // Check if the chunk contains valid UTF-8
                 if let Ok(line) = std::str::from_utf8(chunk) {
-                    // Treat it as a normal string (text line)
-                    let cleaned_line = line.replace('\0', "");
-                    let cleaned_line = if !cleaned_line.is_empty() {
-                        Some(cleaned_line.trim().to_string())
+                    // Treat it as a normal string (text line). Like in the json
+                    // path, the title is cleaned for display while the result keeps
+                    // the line as is, apart from its line break.
+                    let line = line
+                        .strip_suffix('\n')
+                        .map_or(line, |l| l.strip_suffix('\r').unwrap_or(l));
+                    let (title, output) = if !line.is_empty() {
+                        (
+                            Some(sanitize(line).trim().to_string()),
+                            Some(line.to_string()),
+                        )
                     } else {
-                        None
+                        (None, None)
                     };
                     result.push(PipeData {
-                        title: cleaned_line.clone(),
+                        title,
                         description: None,
-                        result: cleaned_line,
+                        result: output,
                         icon: None,
                         icon_size: None,
                         binary: None,
@@ -119,6 +114,14 @@ pub fn deserialize_pipe(mut buf: Vec<u8>) -> Vec<PipeData> {
     }
 }
 
+/// Removes control characters except for tabs and line breaks. Any other valid
+/// unicode, like accented letters, emojis or non-latin scripts, is kept.
+fn sanitize(text: &str) -> String {
+    text.chars()
+        .filter(|&c| !c.is_control() || c == '\t' || c == '\n')
+        .collect()
+}
+
 #[derive(Debug, Deserialize, Clone)]
 pub struct PipeData {
     pub title: Option<String>,
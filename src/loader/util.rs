@@ -1,8 +1,8 @@
 use serde::{Deserialize, Serialize};
-use std::collections::HashSet;
+use std::collections::{HashMap, HashSet};
 use std::env;
 use std::fs::File;
-use std::io::{self, BufRead, BufReader, Read};
+use std::io::{BufReader, Read};
 use std::path::{Path, PathBuf};
 use std::process::Command;
 
@@ -30,18 +30,214 @@ pub struct CommandConfig {
     pub only_home: bool,
     #[serde(default)]
     pub args: serde_json::Value,
+    #[serde(default)]
+    pub typo_tolerance: Option<bool>,
 }
 
 #[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
 pub struct AppData {
+    /// Display name, if the entry is not keyed by it
+    #[serde(default)]
+    pub name: Option<String>,
     pub icon: String,
     pub exec: String,
-    pub search_string: String,
+    #[serde(default, alias = "search_string")]
+    pub keywords: String,
+    /// The untranslated name, if the app is shown under a translated one
+    #[serde(default)]
+    pub untranslated_name: Option<String>,
+    #[serde(default)]
+    pub generic_name: Option<String>,
+    #[serde(default)]
+    pub comment: Option<String>,
+    #[serde(default)]
+    pub categories: Vec<String>,
+    /// Working directory to launch the app in
+    #[serde(default)]
+    pub cwd: Option<String>,
+    /// Additional ways to launch the app, from its `[Desktop Action]` groups
+    #[serde(default)]
+    pub actions: Vec<AppAction>,
     pub tag_start: Option<String>,
     pub tag_end: Option<String>,
     pub desktop_file: Option<PathBuf>,
+    /// Environment variables to launch the app with
+    #[serde(default)]
+    pub env: HashMap<String, String>,
+    /// Replaces the `launch_prefix` of the config. Empty to launch the app
+    /// without any prefix.
+    #[serde(default)]
+    pub launch_prefix: Option<String>,
+    /// Subtracted from the priority, so a positive boost ranks the app higher
+    #[serde(default)]
+    pub boost: f32,
+    /// Run the exec of a command through `$SHELL -c` instead of splitting it
+    #[serde(default)]
+    pub shell: bool,
+    /// Run in the terminal of `default_apps`. Set for apps by their
+    /// `Terminal` key.
+    #[serde(default)]
+    pub terminal: bool,
+    /// Keep the terminal open after the command exited
+    #[serde(default)]
+    pub hold: bool,
+    /// Actions to run after a command
+    #[serde(default, flatten)]
+    pub callbacks: ActionCallbacks,
+    /// Arguments a command asks for before it runs
+    #[serde(default)]
+    pub args: Vec<CommandArg>,
     #[serde(default)]
     pub priority: f32,
+    #[serde(skip)]
+    pub frecency: f32,
+}
+
+/// A desktop action like "New Private Window", shown as its own result
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub struct AppAction {
+    pub name: String,
+    pub exec: String,
+    #[serde(default)]
+    pub icon: Option<String>,
+    #[serde(skip)]
+    pub frecency: f32,
+}
+
+/// Follow-up actions of a command entry. Each one is either a command string
+/// or an object with a `method` and its fields, which may have callbacks of
+/// its own.
+#[derive(Serialize, Deserialize, Clone, Debug, Default, PartialEq)]
+pub struct ActionCallbacks {
+    /// Run if the command exited with `0`
+    #[serde(default)]
+    pub on_success: Option<serde_json::Value>,
+    /// Run if the command failed or could not be started
+    #[serde(default)]
+    pub on_failure: Option<serde_json::Value>,
+    /// Run after the command in any case
+    #[serde(default)]
+    pub then: Option<serde_json::Value>,
+}
+
+/// A named argument of a command, inserted into its exec as `{arg:<name>}`
+#[derive(Serialize, Deserialize, Clone, Debug, Default, PartialEq)]
+pub struct CommandArg {
+    pub name: String,
+    /// Shown above the input instead of the name
+    #[serde(default)]
+    pub label: Option<String>,
+    #[serde(default, rename = "type")]
+    pub kind: ArgKind,
+    /// Used if the input is left empty. Either a string or a number.
+    #[serde(default)]
+    pub default: Option<serde_json::Value>,
+    /// The allowed values of a `choice`
+    #[serde(default)]
+    pub choices: Vec<String>,
+    /// Bounds of a `number`
+    #[serde(default)]
+    pub min: Option<f64>,
+    #[serde(default)]
+    pub max: Option<f64>,
+    /// Allows the argument to be left empty without a default
+    #[serde(default)]
+    pub optional: bool,
+}
+
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, Default, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub enum ArgKind {
+    #[default]
+    String,
+    Number,
+    Choice,
+    #[serde(alias = "path")]
+    File,
+}
+
+impl CommandArg {
+    pub fn label(&self) -> &str {
+        self.label.as_deref().unwrap_or(&self.name)
+    }
+    /// The default as the text of the input
+    pub fn default_text(&self) -> Option<String> {
+        match self.default.as_ref()? {
+            serde_json::Value::String(text) => Some(text.clone()),
+            serde_json::Value::Null => None,
+            other => Some(other.to_string()),
+        }
+    }
+    /// Hint shown in an empty input
+    pub fn placeholder(&self) -> String {
+        match self.kind {
+            ArgKind::String => String::new(),
+            ArgKind::Number => match (self.min, self.max) {
+                (Some(min), Some(max)) => format!("{} – {}", min, max),
+                (Some(min), None) => format!("≥ {}", min),
+                (None, Some(max)) => format!("≤ {}", max),
+                (None, None) => String::from("Number"),
+            },
+            ArgKind::Choice => self.choices.join(" | "),
+            ArgKind::File => String::from("Path to a file"),
+        }
+    }
+    /// Checks the input of the argument and returns the value to insert into
+    /// the exec, or a message on why it is invalid. Empty inputs fall back to
+    /// the default.
+    pub fn validate(&self, input: &str) -> Result<String, String> {
+        let input = input.trim();
+        let input = match (input.is_empty(), self.default_text()) {
+            (false, _) => input.to_string(),
+            (true, Some(default)) => default,
+            (true, None) if self.optional => return Ok(String::new()),
+            (true, None) => return Err(String::from("Required")),
+        };
+        match self.kind {
+            ArgKind::String => Ok(input),
+            ArgKind::Number => {
+                let number: f64 = input
+                    .parse()
+                    .map_err(|_| format!("\"{}\" is not a number", input))?;
+                if !number.is_finite() {
+                    return Err(format!("\"{}\" is not a number", input));
+                }
+                if let Some(min) = self.min.filter(|min| number < *min) {
+                    return Err(format!("Has to be at least {}", min));
+                }
+                if let Some(max) = self.max.filter(|max| number > *max) {
+                    return Err(format!("Has to be at most {}", max));
+                }
+                Ok(input)
+            }
+            ArgKind::Choice => {
+                if self.choices.contains(&input) {
+                    return Ok(input);
+                }
+                // A unique prefix is enough
+                let lower = input.to_lowercase();
+                let mut matches = self
+                    .choices
+                    .iter()
+                    .filter(|choice| choice.to_lowercase().starts_with(&lower));
+                match (matches.next(), matches.next()) {
+                    (Some(choice), None) => Ok(choice.clone()),
+                    (Some(_), Some(_)) => Err(format!("\"{}\" matches several choices", input)),
+                    _ => Err(format!("Has to be one of: {}", self.choices.join(", "))),
+                }
+            }
+            ArgKind::File => {
+                let path = match home_dir() {
+                    Ok(home) => expand_path(Path::new(&input), &home),
+                    Err(_) => Path::new(&input).to_path_buf(),
+                };
+                match path.is_file() {
+                    true => Ok(path.to_string_lossy().to_string()),
+                    false => Err(format!("No file at \"{}\"", input)),
+                }
+            }
+        }
+    }
 }
 
 #[derive(Clone, Debug, Default)]
@@ -57,14 +253,26 @@ pub struct SherlockFlags {
     pub daemonize: bool,
     pub method: Option<String>,
     pub field: Option<String>,
+    pub explain_ranking: bool,
 }
 
-#[derive(Deserialize, Clone, Debug)]
+/// An entry of `sherlock_alias.json`, see [`AliasRules`](super::alias_loader::AliasRules)
+#[derive(Deserialize, Clone, Debug, Default)]
 pub struct SherlockAlias {
     pub name: Option<String>,
     pub icon: Option<String>,
     pub exec: Option<String>,
     pub keywords: Option<String>,
+    /// Ranks the app higher (or lower, if negative)
+    pub boost: Option<f32>,
+    /// Removes the app from the app launcher
+    pub hidden: Option<bool>,
+    #[serde(default)]
+    pub env: HashMap<String, String>,
+    pub cwd: Option<String>,
+    /// Overrides the `Terminal` key of the desktop file
+    pub terminal: Option<bool>,
+    pub launch_prefix: Option<String>,
 }
 #[allow(dead_code)]
 #[derive(Debug, Clone)]
@@ -81,7 +289,6 @@ pub enum SherlockErrorType {
     DisplayError,
     ConfigError(Option<String>),
     FlagLoadError,
-    RegexError(String),
     CommandExecutionError(String),
     ClipboardError,
     DBusConnectionError,
@@ -89,6 +296,8 @@ pub enum SherlockErrorType {
     DBusMessageConstructError(String),
     HttpRequestError(String),
     SocketRemoveError(String),
+    DatabaseError(PathBuf),
+    FileWatchError(PathBuf),
 }
 
 impl SherlockErrorType {
@@ -149,10 +358,6 @@ impl SherlockErrorType {
             SherlockErrorType::FlagLoadError => {
                 (format!("FlagLoadError"), format!("Failed to load flags"))
             }
-            SherlockErrorType::RegexError(key) => (
-                format!("RegexError"),
-                format!("Failed to compile the regular expression for \"{}\"", key),
-            ),
             SherlockErrorType::CommandExecutionError(cmd) => (
                 format!("CommandExecutionError"),
                 format!("Failed to execute command \"{}\"", cmd),
@@ -177,6 +382,14 @@ impl SherlockErrorType {
                 format!("HttpRequestError"),
                 format!("Failed to get requested source \"{}\"", cmd),
             ),
+            SherlockErrorType::DatabaseError(file) => (
+                "DatabaseError".to_string(),
+                format!("Failed to access database \"{}\"", file.to_string_lossy()),
+            ),
+            SherlockErrorType::FileWatchError(file) => (
+                "FileWatchError".to_string(),
+                format!("Failed to watch \"{}\" for changes", file.to_string_lossy()),
+            ),
         }
     }
 }
@@ -204,6 +417,8 @@ pub struct SherlockConfig {
     pub files: ConfigFiles,
     #[serde(default)]
     pub pipe: ConfigPipe,
+    #[serde(default)]
+    pub ranking: ConfigRanking,
 }
 impl SherlockConfig {
     pub fn default() -> Self {
@@ -216,6 +431,7 @@ impl SherlockConfig {
             binds: ConfigBinds::default(),
             files: ConfigFiles::default(),
             pipe: ConfigPipe { method: None },
+            ranking: ConfigRanking::default(),
         }
     }
 }
@@ -322,6 +538,12 @@ pub struct ConfigBehavior {
     pub animate: bool,
     pub field: Option<String>,
     pub launch_prefix: Option<String>,
+    #[serde(default = "default_true")]
+    pub transliterate: bool,
+    /// Send a desktop notification for actions that fail while the window is
+    /// hidden
+    #[serde(default)]
+    pub notify_errors: bool,
 }
 impl Default for ConfigBehavior {
     fn default() -> Self {
@@ -332,6 +554,8 @@ impl Default for ConfigBehavior {
             animate: true,
             field: None,
             launch_prefix: None,
+            transliterate: true,
+            notify_errors: false,
         }
     }
 }
@@ -369,6 +593,10 @@ pub struct ConfigBinds {
     pub next: Option<String>,
     #[serde(default)]
     pub modifier: Option<String>,
+    #[serde(default)]
+    pub history_prev: Option<String>,
+    #[serde(default)]
+    pub history_next: Option<String>,
 }
 
 #[derive(Deserialize, Debug, Clone, Default)]
@@ -377,6 +605,31 @@ pub struct ConfigPipe {
     pub method: Option<String>,
 }
 
+#[derive(Deserialize, Debug, Clone, Copy)]
+pub struct ConfigRanking {
+    #[serde(default = "default_weight")]
+    pub priority: f32,
+    #[serde(default = "default_weight")]
+    pub match_quality: f32,
+    #[serde(default = "default_weight")]
+    pub frecency: f32,
+    #[serde(default = "default_weight")]
+    pub learned: f32,
+    #[serde(default)]
+    pub explain: bool,
+}
+impl Default for ConfigRanking {
+    fn default() -> Self {
+        Self {
+            priority: default_weight(),
+            match_quality: default_weight(),
+            frecency: default_weight(),
+            learned: default_weight(),
+            explain: false,
+        }
+    }
+}
+
 pub fn read_file(file_path: &str) -> std::io::Result<String> {
     let file = File::open(file_path)?;
     let mut reader = BufReader::new(file);
@@ -385,14 +638,6 @@ pub fn read_file(file_path: &str) -> std::io::Result<String> {
     Ok(content)
 }
 
-pub fn read_lines<P>(filename: P) -> io::Result<io::Lines<io::BufReader<File>>>
-where
-    P: AsRef<Path>,
-{
-    let file = File::open(filename)?;
-    Ok(io::BufReader::new(file).lines())
-}
-
 pub fn home_dir() -> Result<PathBuf, SherlockError> {
     env::var("HOME")
         .map_err(|e| SherlockError {
@@ -401,8 +646,9 @@ pub fn home_dir() -> Result<PathBuf, SherlockError> {
         })
         .map(|s| PathBuf::from(s))
 }
-pub fn parse_priority(priority: f32, count: f32, decimals: i32) -> f32 {
-    priority + 1.0 - count * 10f32.powi(-decimals)
+pub fn frecency_score(frecency: f32) -> f32 {
+    // Maps the unbounded frecency into [0, 1)
+    frecency / (frecency + 1.0)
 }
 
 pub fn expand_path(path: &Path, home: &Path) -> PathBuf {
@@ -415,6 +661,14 @@ pub fn expand_path(path: &Path, home: &Path) -> PathBuf {
     path.to_path_buf()
 }
 
+/// The file a new version of `path` is written to before it replaces it,
+/// e.g. `frecency.json.tmp`
+pub fn tmp_path(path: &Path) -> PathBuf {
+    let mut name = path.file_name().unwrap_or_default().to_os_string();
+    name.push(".tmp");
+    path.with_file_name(name)
+}
+
 // ====================
 // SECTION: DEFAULT GETTERS
 // ====================
@@ -441,7 +695,7 @@ pub fn default_currency() -> String {
 }
 
 pub fn default_cache() -> PathBuf {
-    PathBuf::from("~/.cache/sherlock_desktop_cache.json")
+    PathBuf::from("~/.cache/sherlock_desktop_cache.bin")
 }
 pub fn default_config() -> PathBuf {
     PathBuf::from("~/.config/sherlock/config.toml")
@@ -462,6 +716,9 @@ pub fn default_ignore() -> PathBuf {
 pub fn default_true() -> bool {
     true
 }
+pub fn default_weight() -> f32 {
+    1.0
+}
 pub fn default_icon_size() -> i32 {
     22
 }
@@ -520,3 +777,43 @@ fn is_terminal_installed(terminal: &str) -> bool {
         .output()
         .is_ok()
 }
+
+#[test]
+fn test_validate_command_arg() {
+    let number = CommandArg {
+        name: String::from("count"),
+        kind: ArgKind::Number,
+        default: Some(serde_json::json!(5)),
+        min: Some(1.0),
+        ..Default::default()
+    };
+    assert_eq!(number.validate(""), Ok(String::from("5")));
+    assert_eq!(number.validate(" 2.5 "), Ok(String::from("2.5")));
+    assert!(number.validate("0").is_err());
+    assert!(number.validate("five").is_err());
+    assert!(number.validate("nan").is_err());
+    assert!(number.validate("inf").is_err());
+
+    let choice = CommandArg {
+        name: String::from("quality"),
+        kind: ArgKind::Choice,
+        choices: vec![
+            String::from("low"),
+            String::from("lossless"),
+            String::from("high"),
+        ],
+        ..Default::default()
+    };
+    assert_eq!(choice.validate("hi"), Ok(String::from("high")));
+    assert!(choice.validate("lo").is_err());
+    assert!(choice.validate("").is_err());
+
+    let file = CommandArg {
+        name: String::from("file"),
+        kind: ArgKind::File,
+        optional: true,
+        ..Default::default()
+    };
+    assert_eq!(file.validate(""), Ok(String::new()));
+    assert!(file.validate("/").is_err());
+}
@@ -22,6 +22,7 @@ mod ui;
 use application::lock;
 use application::util::AppState;
 use daemon::daemon::SherlockDaemon;
+use daemon::watcher::AppWatcher;
 use loader::{
     util::{SherlockConfig, SherlockError},
     Loader,
@@ -183,6 +184,11 @@ async fn main() {
                     thread::spawn(move || {
                         let _damon = SherlockDaemon::new(SOCKET_PATH);
                     });
+                    // Keep the apps up to date while the daemon is running
+                    thread::spawn(move || match AppWatcher::new() {
+                        Ok(watcher) => watcher.run(),
+                        Err(e) => eprintln!("Error: {:?}", e),
+                    });
                 }
                 false => {
                     // Show window without daemonizing
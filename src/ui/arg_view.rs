@@ -0,0 +1,168 @@
+use gtk4::{self, gdk::Key, prelude::*, Builder, Entry, EventControllerKey, Image, Label};
+use gtk4::{Box as HVBox, Orientation};
+use std::collections::HashMap;
+use std::rc::Rc;
+
+use super::util::show_stack_page;
+use crate::actions::execute_from_attrs;
+use crate::g_subclasses::sherlock_row::SherlockRow;
+use crate::loader::util::CommandArg;
+use crate::APP_STATE;
+
+struct ArgInput {
+    arg: CommandArg,
+    entry: Entry,
+    error: Label,
+}
+
+/// Shows a page with one input per argument of a command. Once every input is
+/// valid, the command is run with its `{arg:<name>}` placeholders filled in.
+pub fn display_args(row: &SherlockRow, args: &[CommandArg], attrs: HashMap<String, String>) {
+    let builder = Builder::from_resource("/dev/skxxtz/sherlock/ui/arg_view.ui");
+    let vbox: HVBox = builder.object("vbox").unwrap();
+    let icon: Image = builder.object("icon").unwrap_or_default();
+    let title: Label = builder.object("title").unwrap_or_default();
+    let input_holder: HVBox = builder.object("inputs").unwrap_or_default();
+
+    title.set_text(attrs.get("name").map_or("", |s| s.as_str()));
+    icon.set_icon_name(attrs.get("icon").map(|s| s.as_str()));
+
+    let inputs: Vec<ArgInput> = args
+        .iter()
+        .map(|arg| {
+            let label = Label::new(Some(arg.label()));
+            label.set_xalign(0.0);
+            label.add_css_class("arg-label");
+
+            let entry = Entry::new();
+            entry.add_css_class("arg-input");
+            entry.set_placeholder_text(Some(&arg.placeholder()));
+            if let Some(default) = arg.default_text() {
+                entry.set_text(&default);
+            }
+
+            let error = Label::new(None);
+            error.set_xalign(0.0);
+            error.add_css_class("arg-error");
+            error.set_visible(false);
+
+            let holder = HVBox::new(Orientation::Vertical, 4);
+            holder.append(&label);
+            holder.append(&entry);
+            holder.append(&error);
+            input_holder.append(&holder);
+
+            // An error is cleared as soon as its input changes
+            let error_clone = error.clone();
+            entry.connect_changed(move |entry| {
+                entry.remove_css_class("error");
+                error_clone.set_visible(false);
+            });
+            ArgInput {
+                arg: arg.clone(),
+                entry,
+                error,
+            }
+        })
+        .collect();
+    let first = inputs.first().map(|input| input.entry.clone());
+
+    nav_event(&vbox, Rc::new(inputs), row.clone(), attrs);
+    APP_STATE.with(|state| {
+        if let Some(ref state) = *state.borrow() {
+            if let Some(stack) = &state.stack {
+                // Replace the page of a command that was opened before
+                if let Some(page) = stack.child_by_name("arg-page") {
+                    stack.remove(&page);
+                }
+                stack.add_named(&vbox, Some("arg-page"));
+            }
+        }
+    });
+    show_stack_page("arg-page", Some(gtk4::StackTransitionType::SlideLeft));
+    if let Some(entry) = first {
+        entry.grab_focus();
+    }
+}
+
+fn nav_event(
+    page: &HVBox,
+    inputs: Rc<Vec<ArgInput>>,
+    row: SherlockRow,
+    attrs: HashMap<String, String>,
+) {
+    let event_controller = EventControllerKey::new();
+    event_controller.set_propagation_phase(gtk4::PropagationPhase::Capture);
+    event_controller.connect_key_pressed(move |_, key, _, _| {
+        let focused = inputs
+            .iter()
+            .position(|input| input.entry.focus_child().is_some());
+        match key {
+            Key::Return | Key::KP_Enter => {
+                if let Some(attrs) = fill_args(&inputs, &attrs) {
+                    back_to_search();
+                    execute_from_attrs(&row, &attrs);
+                }
+                true.into()
+            }
+            Key::Up => {
+                if let Some(input) = focused.and_then(|i| inputs.get(i.checked_sub(1)?)) {
+                    input.entry.grab_focus();
+                }
+                true.into()
+            }
+            Key::Down => {
+                if let Some(input) = focused.and_then(|i| inputs.get(i + 1)) {
+                    input.entry.grab_focus();
+                }
+                true.into()
+            }
+            Key::BackSpace if focused.is_some_and(|i| inputs[i].entry.text().is_empty()) => {
+                back_to_search();
+                true.into()
+            }
+            _ => false.into(),
+        }
+    });
+    page.add_controller(event_controller);
+}
+
+/// Validates every input and returns the attrs of the command with the values
+/// of its arguments. Invalid inputs are marked instead and the first one is
+/// focused.
+fn fill_args(
+    inputs: &[ArgInput],
+    attrs: &HashMap<String, String>,
+) -> Option<HashMap<String, String>> {
+    let mut attrs = attrs.clone();
+    attrs.remove("args");
+    let mut valid = true;
+    for input in inputs {
+        match input.arg.validate(&input.entry.text()) {
+            Ok(value) => {
+                attrs.insert(format!("arg:{}", input.arg.name), value);
+            }
+            Err(message) => {
+                if valid {
+                    input.entry.grab_focus();
+                }
+                valid = false;
+                input.entry.add_css_class("error");
+                input.error.set_text(&message);
+                input.error.set_visible(true);
+            }
+        }
+    }
+    valid.then_some(attrs)
+}
+
+fn back_to_search() {
+    show_stack_page("search-page", Some(gtk4::StackTransitionType::SlideRight));
+    APP_STATE.with(|state| {
+        if let Some(ref state) = *state.borrow() {
+            if let Some(search_bar) = &state.search_bar {
+                search_bar.grab_focus();
+            }
+        }
+    });
+}
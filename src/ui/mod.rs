@@ -1,5 +1,6 @@
 pub mod tiles;
 
+pub mod arg_view;
 pub mod error_view;
 pub mod search;
 pub mod user;
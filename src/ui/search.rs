@@ -15,7 +15,9 @@ use super::tiles::util::AsyncLauncherTile;
 use super::util::*;
 use crate::actions::execute_from_attrs;
 use crate::g_subclasses::sherlock_row::SherlockRow;
-use crate::launcher::{construct_tiles, Launcher, ResultItem};
+use crate::launcher::category_launcher::category_name;
+use crate::launcher::{construct_tiles, Launcher, LauncherType, ResultItem};
+use crate::loader::history_loader::HistoryReader;
 use crate::{AppState, APP_STATE, CONFIG};
 
 #[allow(dead_code)]
@@ -73,7 +75,10 @@ pub fn search(launchers: &Vec<Launcher>, window: &ApplicationWindow) {
 
             if let (Some(mut state), Some(mut parameter)) = (state, parameter) {
                 parameter.push_str(" ");
-                let mode_name = modes_clone.get(&parameter);
+                let mode_name = modes_clone
+                    .get(&parameter)
+                    .cloned()
+                    .or_else(|| sub_mode_name(&modes_clone, &parameter));
                 match mode_name {
                     Some(name) => {
                         ui.search_icon_holder.set_css_classes(&["back"]);
@@ -103,6 +108,15 @@ pub fn search(launchers: &Vec<Launcher>, window: &ApplicationWindow) {
     window.add_action_entries([mode_action]);
 }
 
+/// Title of a sub-mode like `apps/AudioVideo/Audio`: the name of the launcher
+/// of its alias and its last category
+fn sub_mode_name(modes: &HashMap<String, String>, mode: &str) -> Option<String> {
+    let (alias, path) = mode.trim().split_once('/')?;
+    let name = modes.get(&format!("{} ", alias))?;
+    let category = path.rsplit('/').next().filter(|c| !c.is_empty())?;
+    Some(format!("{} › {}", name, category_name(category)))
+}
+
 fn construct_window(
     launchers: &Vec<Launcher>,
 ) -> (
@@ -176,6 +190,51 @@ fn construct_window(
     (mode, modes, vbox, ui, results)
 }
 
+/// Position while cycling through the query history of the current mode
+#[derive(Default)]
+struct HistoryCursor {
+    queries: Vec<String>,
+    index: Option<usize>,
+    /// Text of the search bar before the history was entered
+    draft: String,
+}
+impl HistoryCursor {
+    fn older(&mut self, search_bar: &Entry, mode: &str) {
+        // Start over if the user edited the query in the meantime
+        let current = self.index.and_then(|i| self.queries.get(i));
+        if current.is_none_or(|q| *q != search_bar.text()) {
+            self.queries = HistoryReader::new()
+                .and_then(|reader| reader.queries(mode))
+                .unwrap_or_default();
+            self.index = None;
+            self.draft = search_bar.text().to_string();
+        }
+        let next = self.index.map_or(0, |i| i + 1);
+        if let Some(query) = self.queries.get(next) {
+            self.index = Some(next);
+            set_search_text(query);
+        }
+    }
+    fn newer(&mut self, search_bar: &Entry) {
+        let current = self.index.and_then(|i| self.queries.get(i));
+        if current.is_none_or(|q| *q != search_bar.text()) {
+            self.index = None;
+            return;
+        }
+        match self.index {
+            Some(0) => {
+                self.index = None;
+                set_search_text(&self.draft);
+            }
+            Some(i) => {
+                self.index = Some(i - 1);
+                set_search_text(&self.queries[i - 1]);
+            }
+            None => {}
+        }
+    }
+}
+
 fn nav_event(
     results: Rc<ListBox>,
     search_bar: Entry,
@@ -183,10 +242,32 @@ fn nav_event(
     mode: Rc<RefCell<String>>,
     custom_binds: ConfKeys,
 ) {
+    let history = RefCell::new(HistoryCursor::default());
     let event_controller = EventControllerKey::new();
     event_controller.set_propagation_phase(gtk4::PropagationPhase::Capture);
     event_controller.connect_key_pressed(move |_, key, i, modifiers| {
+        // Other pages, like the arguments of a command, handle their own keys
+        if !search_bar.is_mapped() {
+            return false.into();
+        }
         match key {
+            k if Some(k) == custom_binds.history_prev
+                && custom_binds
+                    .history_prev_mod
+                    .is_none_or(|m| modifiers.contains(m)) =>
+            {
+                let mode = mode.borrow().trim().to_string();
+                history.borrow_mut().older(&search_bar, &mode);
+                return true.into();
+            }
+            k if Some(k) == custom_binds.history_next
+                && custom_binds
+                    .history_next_mod
+                    .is_none_or(|m| modifiers.contains(m)) =>
+            {
+                history.borrow_mut().newer(&search_bar);
+                return true.into();
+            }
             k if Some(k) == custom_binds.prev
                 && custom_binds
                     .prev_mod
@@ -219,8 +300,13 @@ fn nav_event(
                     let _ = search_bar.set_text("");
                 } else {
                     if ctext.is_empty() && mode.borrow().as_str() != "all" {
+                        // Sub-modes go up one level, all others back to 'all'
+                        let parent = match mode.borrow().trim().rsplit_once('/') {
+                            Some((parent, _)) => parent.to_string(),
+                            None => String::from("all"),
+                        };
                         let _ = search_bar
-                            .activate_action("win.switch-mode", Some(&"all".to_variant()));
+                            .activate_action("win.switch-mode", Some(&parent.to_variant()));
                     }
                 }
                 results.focus_first();
@@ -466,7 +552,21 @@ pub fn populate(
         launcher_tiles.extend(widgets);
     }
 
-    launcher_tiles.sort_by(|a, b| a.priority.partial_cmp(&b.priority).unwrap());
+    launcher_tiles.sort_by(|a, b| a.priority.total_cmp(&b.priority));
+
+    // The query is remembered once a result was launched successfully. Picks
+    // from the history itself are not recorded, their query is put back into
+    // the search bar instead.
+    let mode = mode.trim();
+    let in_history = launchers.iter().any(|launcher| {
+        matches!(launcher.launcher_type, LauncherType::History(_))
+            && launcher.alias.as_deref() == Some(mode)
+    });
+    if !in_history && !keyword.trim().is_empty() {
+        for widget in launcher_tiles.iter() {
+            widget.row_item.set_search(keyword, mode);
+        }
+    }
 
     if let Some(c) = CONFIG.get() {
         let mut shortcut_index = 1;
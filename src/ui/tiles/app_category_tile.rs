@@ -0,0 +1,103 @@
+use gtk4::prelude::*;
+use std::collections::HashMap;
+
+use crate::actions::{execute_from_attrs, get_attrs_map};
+use crate::g_subclasses::sherlock_row::SherlockRow;
+use crate::launcher::category_launcher::{category_icon, category_name, AppCategoryLauncher};
+use crate::launcher::{ranking::Score, Launcher, ResultItem};
+
+use super::fuzzy::{match_fields, SearchField};
+use super::util::TileBuilder;
+use super::Tile;
+
+/// Weight of a hit in the category ID, e.g. `AudioVideo` for "Multimedia"
+const CATEGORY_ID_WEIGHT: f32 = 0.9;
+
+impl Tile {
+    /// Lists the sub-categories of `path` and, below the top level, the apps
+    /// in it. Picking a category switches to the mode `<alias>/<path>`.
+    pub fn app_category_tile(
+        launcher: &Launcher,
+        keyword: &str,
+        categories: &AppCategoryLauncher,
+        path: &[&str],
+        learned: &HashMap<String, f32>,
+    ) -> Vec<ResultItem> {
+        let apps = match categories.apps.read() {
+            Ok(apps) => apps,
+            Err(_) => return Vec::new(),
+        };
+        // Without an alias there is no mode to drill into
+        let alias = match launcher.alias.as_deref() {
+            Some(alias) => alias,
+            None => return Vec::new(),
+        };
+        let mut results: Vec<ResultItem> = Vec::new();
+
+        for (category, count) in AppCategoryLauncher::children(&apps, path) {
+            let name = category_name(&category);
+            let id_field = [SearchField {
+                text: &category,
+                weight: CATEGORY_ID_WEIGHT,
+                contiguous: false,
+            }];
+            let matched = match match_fields(&name, &id_field, keyword, launcher.typo_tolerance) {
+                Some(matched) => matched,
+                None => continue,
+            };
+            let mode = std::iter::once(alias)
+                .chain(path.iter().copied())
+                .chain(std::iter::once(category.as_str()))
+                .collect::<Vec<_>>()
+                .join("/");
+
+            let builder = TileBuilder::new("/dev/skxxtz/sherlock/ui/tile.ui");
+            builder.object.set_spawn_focus(launcher.spawn_focus);
+            builder.object.set_shortcut(launcher.shortcut);
+            if launcher.name.is_empty() {
+                builder.category.set_visible(false);
+            }
+            builder.category.set_text(&launcher.name);
+            builder.icon.set_icon_name(Some(category_icon(&category)));
+            builder.title.set_markup(&matched.name.highlight(&name));
+            builder.display_tag_end(&Some(count.to_string()), keyword);
+
+            let attrs = get_attrs_map(vec![
+                ("method", "categories"),
+                ("exec", &mode),
+                ("keyword", keyword),
+            ]);
+            builder
+                .object
+                .connect("row-should-activate", false, move |row| {
+                    let row = row.first().map(|f| f.get::<SherlockRow>().ok())??;
+                    execute_from_attrs(&row, &attrs);
+                    None
+                });
+
+            let score = Score {
+                priority: launcher.priority as f32,
+                quality: matched.quality,
+                learned: learned.get(&mode).copied().unwrap_or(0.0),
+                ..Default::default()
+            };
+            let shortcut_holder = match launcher.shortcut {
+                true => builder.shortcut_holder,
+                _ => None,
+            };
+            results.push(ResultItem {
+                priority: score.apply(&builder.object),
+                row_item: builder.object,
+                shortcut_holder,
+            });
+        }
+
+        if !path.is_empty() {
+            let mut apps = AppCategoryLauncher::apps_in(&apps, path);
+            apps.values_mut()
+                .for_each(|app| app.priority = categories.priority);
+            results.extend(Tile::app_tile(launcher, keyword, &apps, learned));
+        }
+        results
+    }
+}
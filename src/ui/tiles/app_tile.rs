@@ -3,63 +3,219 @@ use std::collections::HashMap;
 
 use crate::actions::{execute_from_attrs, get_attrs_map};
 use crate::g_subclasses::sherlock_row::SherlockRow;
-use crate::launcher::{Launcher, ResultItem};
+use crate::launcher::{ranking::Score, Launcher, ResultItem};
 use crate::loader::util::AppData;
 
+use super::fuzzy::{match_fields, SearchField};
 use super::util::TileBuilder;
 use super::Tile;
 
+/// Weight of a hit in the app name when matching one of its actions. Below
+/// `1.0`, so actions are listed after their app.
+const ACTION_APP_WEIGHT: f32 = 0.8;
+
 impl Tile {
     pub fn app_tile(
         launcher: &Launcher,
         keyword: &str,
         commands: &HashMap<String, AppData>,
+        learned: &HashMap<String, f32>,
     ) -> Vec<ResultItem> {
         let mut results: Vec<ResultItem> = Default::default();
 
         for (key, value) in commands.into_iter() {
-            if value
-                .search_string
-                .to_lowercase()
-                .contains(&keyword.to_lowercase())
+            let name = value.name.as_ref().unwrap_or(key);
+            // The query is only filled in for display, as it would otherwise
+            // match itself
+            let search_name = name.replace("{keyword}", "");
+            let tile_name = name.replace("{keyword}", keyword);
+            if let Some(mut matched) = value.search(&search_name, keyword, launcher.typo_tolerance)
             {
+                fill_positions(name, keyword, &mut matched.name.positions);
                 let builder = TileBuilder::new("/dev/skxxtz/sherlock/ui/tile.ui");
-                builder.object.set_spawn_focus(launcher.spawn_focus);
-                builder.object.set_shortcut(launcher.shortcut);
-
-                let tile_name = key.replace("{keyword}", keyword);
                 builder.display_tag_start(&value.tag_start, keyword);
                 builder.display_tag_end(&value.tag_end, keyword);
-
-                if launcher.name.is_empty() {
-                    builder.category.set_visible(false);
-                }
-                builder.category.set_text(&launcher.name);
                 builder.icon.set_icon_name(Some(&value.icon));
-                builder.title.set_markup(&tile_name);
+                builder
+                    .title
+                    .set_markup(&matched.name.highlight(&tile_name));
 
-                let attrs =
-                    get_attrs_map(vec![("method", &launcher.method), ("exec", &value.exec)]);
+                let score = Score {
+                    priority: value.priority - value.boost,
+                    quality: matched.quality,
+                    frecency: value.frecency,
+                    learned: learned.get(&value.exec).copied().unwrap_or(0.0),
+                };
+                results.push(app_row(
+                    launcher,
+                    keyword,
+                    builder,
+                    value,
+                    &tile_name,
+                    &value.exec,
+                    score,
+                ));
+            }
 
+            // Actions are only offered once the user searches for something
+            if keyword.trim().is_empty() {
+                continue;
+            }
+            let app_field = [SearchField {
+                text: &search_name,
+                weight: ACTION_APP_WEIGHT,
+                contiguous: false,
+            }];
+            for action in value.actions.iter() {
+                let matched = match match_fields(
+                    &action.name,
+                    &app_field,
+                    keyword,
+                    launcher.typo_tolerance,
+                ) {
+                    Some(matched) => matched,
+                    None => continue,
+                };
+                let builder = TileBuilder::new("/dev/skxxtz/sherlock/ui/tile.ui");
+                builder.display_tag_start(&Some(tile_name.clone()), keyword);
                 builder
-                    .object
-                    .connect("row-should-activate", false, move |row| {
-                        let row = row.first().map(|f| f.get::<SherlockRow>().ok())??;
-                        execute_from_attrs(&row, &attrs);
-                        None
-                    });
-
-                let shortcut_holder = match launcher.shortcut {
-                    true => builder.shortcut_holder,
-                    _ => None,
+                    .icon
+                    .set_icon_name(Some(action.icon.as_ref().unwrap_or(&value.icon)));
+                builder
+                    .title
+                    .set_markup(&matched.name.highlight(&action.name));
+
+                let score = Score {
+                    priority: value.priority - value.boost,
+                    quality: matched.quality,
+                    frecency: action.frecency,
+                    learned: learned.get(&action.exec).copied().unwrap_or(0.0),
                 };
-                results.push(ResultItem {
-                    priority: value.priority,
-                    row_item: builder.object,
-                    shortcut_holder,
-                });
+                results.push(app_row(
+                    launcher,
+                    keyword,
+                    builder,
+                    value,
+                    &tile_name,
+                    &action.exec,
+                    score,
+                ));
             }
         }
         return results;
     }
 }
+
+/// Moves the positions of a match in `name` without its `{keyword}`
+/// placeholders to the name with `keyword` filled in
+fn fill_positions(name: &str, keyword: &str, positions: &mut [usize]) {
+    let len = keyword.chars().count();
+    // Positions in the name without placeholders at which the keyword is filled in
+    let inserts: Vec<usize> = name
+        .split("{keyword}")
+        .scan(0, |start, part| {
+            *start += part.chars().count();
+            Some(*start)
+        })
+        .collect();
+    for position in positions.iter_mut() {
+        let before = inserts[..inserts.len() - 1]
+            .iter()
+            .filter(|i| **i <= *position)
+            .count();
+        *position += before * len;
+    }
+}
+
+/// Finishes a tile that launches `exec` in the context of the app `value`,
+/// shown as `name`
+fn app_row(
+    launcher: &Launcher,
+    keyword: &str,
+    builder: TileBuilder,
+    value: &AppData,
+    name: &str,
+    exec: &str,
+    score: Score,
+) -> ResultItem {
+    builder.object.set_spawn_focus(launcher.spawn_focus);
+    builder.object.set_shortcut(launcher.shortcut);
+
+    if launcher.name.is_empty() {
+        builder.category.set_visible(false);
+    }
+    builder.category.set_text(&launcher.name);
+
+    let mut attrs = get_attrs_map(vec![
+        ("method", &launcher.method),
+        ("exec", exec),
+        ("keyword", keyword),
+    ]);
+    if let Some(cwd) = &value.cwd {
+        attrs.insert(String::from("cwd"), cwd.to_string());
+    }
+    // Values for the field codes of the exec
+    attrs.insert(String::from("name"), name.to_string());
+    attrs.insert(String::from("icon"), value.icon.to_string());
+    if let Some(path) = value.desktop_file.as_ref().and_then(|p| p.to_str()) {
+        attrs.insert(String::from("desktop_file"), path.to_string());
+    }
+    if let Some(prefix) = &value.launch_prefix {
+        attrs.insert(String::from("launch_prefix"), prefix.to_string());
+    }
+    let flags = [
+        ("shell", value.shell),
+        ("terminal", value.terminal),
+        ("hold", value.hold),
+    ];
+    for (key, _) in flags.iter().filter(|(_, set)| *set) {
+        attrs.insert(key.to_string(), String::from("true"));
+    }
+    let callbacks = [
+        ("on_success", &value.callbacks.on_success),
+        ("on_failure", &value.callbacks.on_failure),
+        ("then", &value.callbacks.then),
+    ];
+    for (key, callback) in callbacks {
+        if let Some(callback) = callback {
+            attrs.insert(key.to_string(), callback.to_string());
+        }
+    }
+    if !value.args.is_empty() {
+        if let Ok(args) = serde_json::to_string(&value.args) {
+            attrs.insert(String::from("args"), args);
+        }
+    }
+    for (key, val) in value.env.iter() {
+        attrs.insert(format!("env:{}", key), val.to_string());
+    }
+
+    builder
+        .object
+        .connect("row-should-activate", false, move |row| {
+            let row = row.first().map(|f| f.get::<SherlockRow>().ok())??;
+            execute_from_attrs(&row, &attrs);
+            None
+        });
+
+    let shortcut_holder = match launcher.shortcut {
+        true => builder.shortcut_holder,
+        _ => None,
+    };
+    ResultItem {
+        priority: score.apply(&builder.object),
+        row_item: builder.object,
+        shortcut_holder,
+    }
+}
+
+#[test]
+fn test_fill_positions() {
+    let mut positions = vec![0, 7, 8];
+    fill_positions("Search {keyword} web", "rust", &mut positions);
+    assert_eq!(positions, vec![0, 11, 12]);
+
+    let mut positions = vec![0];
+    fill_positions("{keyword}{keyword}x", "ab", &mut positions);
+    assert_eq!(positions, vec![4]);
+}
@@ -7,7 +7,7 @@ use std::vec;
 use crate::actions::{execute_from_attrs, get_attrs_map};
 use crate::g_subclasses::sherlock_row::SherlockRow;
 use crate::launcher::bulk_text_launcher::BulkText;
-use crate::launcher::{Launcher, ResultItem};
+use crate::launcher::{ranking::Score, Launcher, ResultItem};
 
 use super::util::{AsyncOptions, TileBuilder};
 use super::Tile;
@@ -42,7 +42,7 @@ impl Tile {
             _ => None,
         };
         let res = ResultItem {
-            priority: launcher.priority as f32,
+            priority: Score::new(launcher.priority as f32).apply(&builder.object),
             row_item: builder.object,
             shortcut_holder,
         };
@@ -82,7 +82,7 @@ impl Tile {
                 None
             });
         let res = ResultItem {
-            priority: launcher.priority as f32,
+            priority: Score::new(launcher.priority as f32).apply(&builder.object),
             row_item: builder.object,
             shortcut_holder,
         };
@@ -8,7 +8,7 @@ use super::Tile;
 use crate::{
     actions::{execute_from_attrs, get_attrs_map},
     g_subclasses::sherlock_row::SherlockRow,
-    launcher::{calc_launcher::Calculator, Launcher, ResultItem},
+    launcher::{calc_launcher::Calculator, ranking::Score, Launcher, ResultItem},
 };
 
 impl Tile {
@@ -82,7 +82,7 @@ impl Tile {
             };
 
             let res = ResultItem {
-                priority: launcher.priority as f32,
+                priority: Score::new(launcher.priority as f32).apply(&builder.object),
                 row_item: builder.object,
                 shortcut_holder,
             };
@@ -7,7 +7,7 @@ use crate::actions::{execute_from_attrs, get_attrs_map};
 use crate::g_subclasses::sherlock_row::SherlockRow;
 use crate::launcher::calc_launcher::Calculator;
 use crate::launcher::clipboard_launcher::ClipboardLauncher;
-use crate::launcher::{Launcher, ResultItem};
+use crate::launcher::{ranking::Score, Launcher, ResultItem};
 
 use super::util::TileBuilder;
 use super::Tile;
@@ -145,7 +145,7 @@ impl Tile {
                     _ => None,
                 };
                 results.push(ResultItem {
-                    priority: launcher.priority as f32,
+                    priority: Score::new(launcher.priority as f32).apply(&builder.object),
                     row_item: builder.object,
                     shortcut_holder,
                 });
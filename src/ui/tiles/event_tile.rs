@@ -8,7 +8,7 @@ use super::Tile;
 use crate::actions::{execute_from_attrs, get_attrs_map};
 use crate::g_subclasses::sherlock_row::SherlockRow;
 use crate::launcher::event_launcher::EventLauncher;
-use crate::launcher::{Launcher, ResultItem};
+use crate::launcher::{ranking::Score, Launcher, ResultItem};
 
 impl Tile {
     pub fn event_tile(
@@ -62,7 +62,7 @@ impl Tile {
             _ => None,
         };
         let res = ResultItem {
-            priority: launcher.priority as f32,
+            priority: Score::new(launcher.priority as f32).apply(&builder.object),
             row_item: builder.object,
             shortcut_holder,
         };
@@ -0,0 +1,557 @@
+use deunicode::deunicode_char;
+use unicode_normalization::char::{decompose_compatible, is_combining_mark};
+
+use crate::CONFIG;
+
+// Scoring constants, modelled after fzf's algorithm
+const SCORE_MATCH: i32 = 16;
+const SCORE_GAP_START: i32 = -3;
+const SCORE_GAP_EXTENSION: i32 = -1;
+const BONUS_BOUNDARY: i32 = SCORE_MATCH / 2;
+const BONUS_BOUNDARY_WHITE: i32 = BONUS_BOUNDARY + 2;
+const BONUS_BOUNDARY_DELIMITER: i32 = BONUS_BOUNDARY + 1;
+const BONUS_NON_WORD: i32 = SCORE_MATCH / 2;
+const BONUS_CAMEL: i32 = BONUS_BOUNDARY + SCORE_GAP_EXTENSION;
+const BONUS_CONSECUTIVE: i32 = -(SCORE_GAP_START + SCORE_GAP_EXTENSION);
+const BONUS_FIRST_CHAR_MULTIPLIER: i32 = 2;
+/// Quality of a typo match with zero edits, scaled down by the number of edits
+const TYPO_QUALITY: f32 = 0.4;
+
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct FuzzyMatch {
+    pub score: i32,
+    /// Char indices (not byte indices) of the matched characters
+    pub positions: Vec<usize>,
+    /// Number of matched pattern characters. May exceed the number of positions
+    /// if several of them stem from the same character, e.g. in transliterations.
+    pub matched: usize,
+}
+impl FuzzyMatch {
+    /// Normalizes the score into `0.0..=1.0` relative to the best score a
+    /// pattern of the same length could possibly reach.
+    pub fn quality(&self) -> f32 {
+        let len = self.matched as i32;
+        if len == 0 {
+            return 0.0;
+        }
+        let ideal = len * SCORE_MATCH
+            + BONUS_BOUNDARY_WHITE * BONUS_FIRST_CHAR_MULTIPLIER
+            + (len - 1) * BONUS_BOUNDARY_WHITE;
+        (self.score as f32 / ideal as f32).clamp(0.0, 1.0)
+    }
+    pub fn is_contiguous(&self) -> bool {
+        self.positions.windows(2).all(|w| w[1] == w[0] + 1)
+    }
+    /// Returns `text` as escaped Pango markup with the matched characters
+    /// wrapped in bold tags. `text` has to be the string that was matched.
+    pub fn highlight(&self, text: &str) -> String {
+        let mut markup = String::with_capacity(text.len() + self.positions.len() * 7);
+        let mut positions = self.positions.iter().peekable();
+        let mut open = false;
+        for (i, c) in text.chars().enumerate() {
+            let matched = positions.next_if(|p| **p == i).is_some();
+            if matched != open {
+                markup.push_str(if matched { "<b>" } else { "</b>" });
+                open = matched;
+            }
+            push_escaped(&mut markup, c);
+        }
+        if open {
+            markup.push_str("</b>");
+        }
+        markup
+    }
+}
+
+/// A secondary text of a search entry, e.g. its keywords or comment
+pub struct SearchField<'a> {
+    pub text: &'a str,
+    pub weight: f32,
+    /// Only accept the match if the token appears as a contiguous substring
+    pub contiguous: bool,
+}
+
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct FieldMatch {
+    /// Averaged, weighted quality of all query tokens in `0.0..=1.0`
+    pub quality: f32,
+    /// Positions within the name that were hit by any of the tokens
+    pub name: FuzzyMatch,
+}
+
+/// Splits the query into whitespace separated tokens and matches each of them
+/// against the name and the secondary fields. Every token has to match at least
+/// one of them (AND semantics). A token counts with its best weighted match;
+/// name hits have a weight of `1.0`. If `typo_tolerant` is set, tokens that do
+/// not match at all are compared to the words of the name and the
+/// non-contiguous fields by edit distance as a last resort.
+pub fn match_fields(
+    name: &str,
+    fields: &[SearchField],
+    query: &str,
+    typo_tolerant: bool,
+) -> Option<FieldMatch> {
+    let mut quality = 0.0;
+    let mut tokens = 0;
+    let mut name_match = FuzzyMatch::default();
+
+    for token in query.split_whitespace() {
+        tokens += 1;
+        let mut best: Option<f32> = None;
+        if let Some(m) = fuzzy_score(name, token) {
+            best = Some(m.quality());
+            name_match.score += m.score;
+            name_match.matched += m.matched;
+            name_match.positions.extend(m.positions);
+        }
+        for field in fields {
+            let field_quality = fuzzy_score(field.text, token)
+                .filter(|m| !field.contiguous || m.is_contiguous())
+                .map(|m| m.quality() * field.weight);
+            best = match (best, field_quality) {
+                (Some(b), Some(f)) => Some(b.max(f)),
+                (b, f) => b.or(f),
+            };
+        }
+        if best.is_none() && typo_tolerant {
+            let name_field = SearchField {
+                text: name,
+                weight: 1.0,
+                contiguous: false,
+            };
+            best = std::iter::once(&name_field)
+                .chain(fields.iter().filter(|f| !f.contiguous))
+                .filter_map(|f| typo_score(f.text, token).map(|q| q * f.weight))
+                .reduce(f32::max);
+        }
+        quality += best?;
+    }
+
+    name_match.positions.sort_unstable();
+    name_match.positions.dedup();
+    Some(FieldMatch {
+        quality: if tokens > 0 {
+            quality / tokens as f32
+        } else {
+            0.0
+        },
+        name: name_match,
+    })
+}
+
+/// Number of edits tolerated for a token of the given length
+fn max_typos(len: usize) -> usize {
+    match len {
+        0..=3 => 0,
+        4..=7 => 1,
+        _ => 2,
+    }
+}
+
+/// Compares the token to every word of `text` and returns the quality of the
+/// closest word, if it is within the tolerated number of edits.
+pub fn typo_score(text: &str, token: &str) -> Option<f32> {
+    let token = Normalized::new(token, false).folded;
+    let max = max_typos(token.len());
+    if max == 0 {
+        return None;
+    }
+    text.split(|c: char| !c.is_alphanumeric())
+        .filter(|w| !w.is_empty())
+        .filter_map(|word| {
+            let word = Normalized::new(word, false).folded;
+            prefix_edit_distance(&token, &word, max)
+        })
+        .min()
+        .map(|d| TYPO_QUALITY * (1.0 - d as f32 / (max + 1) as f32))
+}
+
+/// Optimal string alignment distance (Damerau-Levenshtein with adjacent
+/// transpositions) between `token` and the closest prefix of `word`, so that
+/// partially typed words are found as well. Returns `None` if the distance
+/// exceeds `max`.
+fn prefix_edit_distance(token: &[char], word: &[char], max: usize) -> Option<usize> {
+    let (m, n) = (token.len(), word.len());
+    if n + max < m {
+        return None;
+    }
+    // Three rolling rows: i - 2, i - 1 and i
+    let mut prev2: Vec<usize> = vec![0; n + 1];
+    let mut prev: Vec<usize> = (0..=n).collect();
+    let mut row: Vec<usize> = vec![0; n + 1];
+    for i in 1..=m {
+        row[0] = i;
+        for j in 1..=n {
+            let cost = usize::from(token[i - 1] != word[j - 1]);
+            row[j] = (prev[j] + 1).min(row[j - 1] + 1).min(prev[j - 1] + cost);
+            if i > 1 && j > 1 && token[i - 1] == word[j - 2] && token[i - 2] == word[j - 1] {
+                row[j] = row[j].min(prev2[j - 2] + 1);
+            }
+        }
+        if row.iter().min().is_some_and(|d| *d > max) {
+            return None;
+        }
+        std::mem::swap(&mut prev2, &mut prev);
+        std::mem::swap(&mut prev, &mut row);
+    }
+    // `prev` now holds the last row: distances of the full token to every prefix
+    prev.into_iter().min().filter(|d| *d <= max)
+}
+
+/// Escapes the characters that have a special meaning in Pango markup.
+fn push_escaped(buf: &mut String, c: char) {
+    match c {
+        '&' => buf.push_str("&amp;"),
+        '<' => buf.push_str("&lt;"),
+        '>' => buf.push_str("&gt;"),
+        '\'' => buf.push_str("&apos;"),
+        '"' => buf.push_str("&quot;"),
+        c => buf.push(c),
+    }
+}
+
+#[derive(Clone, Copy, PartialEq)]
+enum CharClass {
+    White,
+    Delimiter,
+    NonWord,
+    Lower,
+    Upper,
+    Number,
+    Letter,
+}
+impl CharClass {
+    fn of(c: char) -> Self {
+        match c {
+            c if c.is_whitespace() => CharClass::White,
+            '/' | ',' | ':' | ';' | '|' | '-' | '_' | '.' => CharClass::Delimiter,
+            c if c.is_lowercase() => CharClass::Lower,
+            c if c.is_uppercase() => CharClass::Upper,
+            c if c.is_numeric() => CharClass::Number,
+            c if c.is_alphabetic() => CharClass::Letter,
+            _ => CharClass::NonWord,
+        }
+    }
+    fn is_word(&self) -> bool {
+        !matches!(
+            self,
+            CharClass::White | CharClass::Delimiter | CharClass::NonWord
+        )
+    }
+}
+
+fn bonus_for(prev: CharClass, current: CharClass) -> i32 {
+    if current.is_word() {
+        return match prev {
+            CharClass::White => BONUS_BOUNDARY_WHITE,
+            CharClass::Delimiter => BONUS_BOUNDARY_DELIMITER,
+            CharClass::NonWord => BONUS_BOUNDARY,
+            CharClass::Lower if current == CharClass::Upper => BONUS_CAMEL,
+            CharClass::Number => 0,
+            _ if current == CharClass::Number => BONUS_CAMEL,
+            _ => 0,
+        };
+    }
+    match current {
+        CharClass::White => BONUS_BOUNDARY_WHITE,
+        _ => BONUS_NON_WORD,
+    }
+}
+
+/// Search representation of a text: compatibility decomposed, without
+/// diacritics and lowercased. Each char remembers the index of the char of the
+/// original text it stems from.
+struct Normalized {
+    folded: Vec<char>,
+    class: Vec<CharClass>,
+    origin: Vec<usize>,
+}
+impl Normalized {
+    /// With `transliterate` set, characters of non-latin scripts are replaced
+    /// by their latin transliteration, e.g. `微信` becomes `wei xin`.
+    fn new(text: &str, transliterate: bool) -> Self {
+        let mut normalized = Normalized {
+            folded: Vec::with_capacity(text.len()),
+            class: Vec::with_capacity(text.len()),
+            origin: Vec::with_capacity(text.len()),
+        };
+        for (i, c) in text.chars().enumerate() {
+            if c.is_ascii() {
+                normalized.push(c, i);
+                continue;
+            }
+            decompose_compatible(c, |d| {
+                if is_combining_mark(d) {
+                    return;
+                }
+                match deunicode_char(d).filter(|_| transliterate && !d.is_ascii()) {
+                    Some(latin) => latin.chars().for_each(|l| normalized.push(l, i)),
+                    None => normalized.push(d, i),
+                }
+            });
+        }
+        normalized
+    }
+    fn push(&mut self, c: char, origin: usize) {
+        let class = CharClass::of(c);
+        for lower in c.to_lowercase() {
+            self.folded.push(lower);
+            self.class.push(class);
+            self.origin.push(origin);
+        }
+    }
+}
+
+fn transliterate() -> bool {
+    CONFIG.get().is_none_or(|c| c.behavior.transliterate)
+}
+
+/// Scores how well `pattern` matches `text` as a case-insensitive subsequence.
+/// Consecutive runs and matches on word boundaries are rewarded, gaps between
+/// matched characters are penalized. Returns `None` if the pattern is not a
+/// subsequence of the text. An empty pattern matches everything with a score
+/// of zero.
+///
+/// Both sides are compared without diacritics, so `reseda` finds `Réséda`.
+/// Unless disabled in the config, texts in non-latin scripts are additionally
+/// matched by their transliteration, so `wx` or `weixin` find `微信`.
+pub fn fuzzy_score(text: &str, pattern: &str) -> Option<FuzzyMatch> {
+    let pattern = Normalized::new(pattern, false).folded;
+    if pattern.is_empty() {
+        return Some(FuzzyMatch::default());
+    }
+    let plain = Normalized::new(text, false);
+    let best = score_normalized(&plain, &pattern);
+    if text.is_ascii() || !transliterate() {
+        return best;
+    }
+    let latin = Normalized::new(text, true);
+    if latin.folded == plain.folded {
+        return best;
+    }
+    match (best, score_normalized(&latin, &pattern)) {
+        (Some(b), Some(l)) if l.score > b.score => Some(l),
+        (b, l) => b.or(l),
+    }
+}
+
+fn score_normalized(normalized: &Normalized, pattern: &[char]) -> Option<FuzzyMatch> {
+    let text = &normalized.folded;
+    let (m, n) = (pattern.len(), text.len());
+    if m > n {
+        return None;
+    }
+
+    // Quick rejection before running the full dp
+    let mut pi = 0;
+    for c in text.iter() {
+        if pi < m && *c == pattern[pi] {
+            pi += 1;
+        }
+    }
+    if pi < m {
+        return None;
+    }
+
+    let bonus: Vec<i32> = (0..n)
+        .map(|j| {
+            let prev = match j {
+                0 => CharClass::White,
+                _ => normalized.class[j - 1],
+            };
+            bonus_for(prev, normalized.class[j])
+        })
+        .collect();
+
+    // score[i][j]: best score with pattern[i] matched at text[j]
+    // chunk[i][j]: bonus of the first char of the consecutive run ending at (i, j)
+    // from[i][j]: column pattern[i - 1] was matched at, used for backtracking
+    let mut score: Vec<Vec<Option<i32>>> = vec![vec![None; n]; m];
+    let mut chunk: Vec<Vec<i32>> = vec![vec![0; n]; m];
+    let mut from: Vec<Vec<usize>> = vec![vec![0; n]; m];
+
+    for i in 0..m {
+        // Best (score, column) of the previous row reachable through a gap
+        let mut gap: Option<(i32, usize)> = None;
+        for j in i..n {
+            if i > 0 && j >= 2 {
+                let extended = gap.map(|(s, k)| (s + SCORE_GAP_EXTENSION, k));
+                let started = score[i - 1][j - 2].map(|s| (s + SCORE_GAP_START, j - 2));
+                gap = match (extended, started) {
+                    (Some(e), Some(s)) if s.0 >= e.0 => Some(s),
+                    (Some(e), _) => Some(e),
+                    (None, s) => s,
+                };
+            }
+            if text[j] != pattern[i] {
+                continue;
+            }
+
+            if i == 0 {
+                score[i][j] = Some(SCORE_MATCH + bonus[j] * BONUS_FIRST_CHAR_MULTIPLIER);
+                chunk[i][j] = bonus[j];
+                continue;
+            }
+
+            let consecutive = match j {
+                0 => None,
+                _ => score[i - 1][j - 1].map(|s| {
+                    let run_bonus = chunk[i - 1][j - 1].max(BONUS_CONSECUTIVE).max(bonus[j]);
+                    (s + SCORE_MATCH + run_bonus, run_bonus)
+                }),
+            };
+            let gapped = gap.map(|(s, k)| (s + SCORE_MATCH + bonus[j], k));
+
+            match (consecutive, gapped) {
+                (Some((c, run_bonus)), Some((g, _))) if c >= g => {
+                    score[i][j] = Some(c);
+                    chunk[i][j] = run_bonus;
+                    from[i][j] = j - 1;
+                }
+                (_, Some((g, k))) => {
+                    score[i][j] = Some(g);
+                    chunk[i][j] = bonus[j];
+                    from[i][j] = k;
+                }
+                (Some((c, run_bonus)), None) => {
+                    score[i][j] = Some(c);
+                    chunk[i][j] = run_bonus;
+                    from[i][j] = j - 1;
+                }
+                (None, None) => {}
+            }
+        }
+    }
+
+    let (best, mut j) = score[m - 1]
+        .iter()
+        .enumerate()
+        .filter_map(|(j, s)| s.map(|s| (s, j)))
+        .max_by(|a, b| a.0.cmp(&b.0).then(b.1.cmp(&a.1)))?;
+
+    let mut positions = vec![0; m];
+    for i in (0..m).rev() {
+        positions[i] = normalized.origin[j];
+        j = from[i][j];
+    }
+    positions.dedup();
+    Some(FuzzyMatch {
+        score: best,
+        positions,
+        matched: m,
+    })
+}
+
+#[test]
+fn test_fuzzy_score() {
+    // Subsequences across word boundaries are found
+    let firefox = fuzzy_score("Firefox Web Browser", "ffx").expect("ffx should match");
+    assert_eq!(firefox.positions, vec![0, 4, 6]);
+    assert!(fuzzy_score("Firefox", "xff").is_none());
+
+    // Prefix matches outrank matches inside a word
+    let code = fuzzy_score("Code", "code").unwrap();
+    let barcode = fuzzy_score("Barcode Reader", "code").unwrap();
+    assert!(code.score > barcode.score);
+    assert!(code.quality() > barcode.quality());
+    assert_eq!(barcode.positions, vec![3, 4, 5, 6]);
+
+    // Boundary matches are preferred over earlier inner matches
+    let vsc = fuzzy_score("visual studio code", "vsc").unwrap();
+    assert_eq!(vsc.positions, vec![0, 7, 14]);
+
+    assert_eq!(fuzzy_score("anything", ""), Some(FuzzyMatch::default()));
+}
+
+#[test]
+fn test_normalization() {
+    // Diacritics are ignored on both sides
+    let reseda = fuzzy_score("Réséda", "reseda").unwrap();
+    assert_eq!(reseda.positions, vec![0, 1, 2, 3, 4, 5]);
+    assert_eq!(
+        reseda.quality(),
+        fuzzy_score("Reseda", "reseda").unwrap().quality()
+    );
+    assert!(fuzzy_score("Reseda", "réséda").is_some());
+
+    // Compatibility characters are decomposed
+    assert!(fuzzy_score("ﬁle manager", "file").is_some());
+
+    // Non-latin scripts match by their own characters and their transliteration
+    assert!(fuzzy_score("Телеграм", "теле").is_some());
+    assert!(fuzzy_score("Телеграм", "telegram").is_some());
+    let wechat = fuzzy_score("微信", "wx").unwrap();
+    assert_eq!(wechat.positions, vec![0, 1]);
+    assert_eq!(wechat.highlight("微信"), "<b>微信</b>");
+    let wechat = fuzzy_score("微信", "weixin").unwrap();
+    assert_eq!(wechat.positions, vec![0, 1]);
+    assert!(wechat.quality() <= 1.0);
+}
+
+#[test]
+fn test_highlight() {
+    let text = "Tom & Jerry <Deluxe>";
+    let matched = fuzzy_score(text, "tom").unwrap();
+    assert_eq!(
+        matched.highlight(text),
+        "<b>Tom</b> &amp; Jerry &lt;Deluxe&gt;"
+    );
+    let matched = fuzzy_score(text, "tjd").unwrap();
+    assert_eq!(
+        matched.highlight(text),
+        "<b>T</b>om &amp; <b>J</b>erry &lt;<b>D</b>eluxe&gt;"
+    );
+    assert_eq!(
+        FuzzyMatch::default().highlight(text),
+        "Tom &amp; Jerry &lt;Deluxe&gt;"
+    );
+}
+
+#[test]
+fn test_match_fields() {
+    let keywords = [SearchField {
+        text: "Text",
+        weight: 0.7,
+        contiguous: false,
+    }];
+    let comment = [SearchField {
+        text: "Edit text files",
+        weight: 0.5,
+        contiguous: true,
+    }];
+
+    // Tokens may appear in any order and in different fields
+    let matched = match_fields("Text Editor", &[], "editor text", false).unwrap();
+    assert_eq!(matched.name.positions.len(), 10);
+    let matched = match_fields("gedit", &comment, "text edit", false).unwrap();
+    assert_eq!(matched.name.positions, vec![1, 2, 3, 4]);
+
+    // Every token has to match
+    assert!(match_fields("gedit", &comment, "text spreadsheet", false).is_none());
+
+    // Scattered hits in contiguous fields are rejected
+    assert!(match_fields("gedit", &comment, "xfs", false).is_none());
+
+    // Name hits weigh more than keyword hits
+    let by_name = match_fields("Text", &[], "text", false).unwrap();
+    let by_keyword = match_fields("Editor", &keywords, "text", false).unwrap();
+    assert!(by_name.quality > by_keyword.quality);
+}
+
+#[test]
+fn test_typo_tolerance() {
+    // Missing, transposed and wrong characters are tolerated
+    assert!(match_fields("Firefox", &[], "fierfox", true).is_some());
+    assert!(match_fields("Thunderbird Mail", &[], "thunderbrid", true).is_some());
+    assert!(match_fields("Firefox", &[], "firw", true).is_some());
+    assert!(match_fields("Firefox", &[], "fierfox", false).is_none());
+
+    // Short tokens and too many edits are not
+    assert!(match_fields("Code", &[], "cdx", true).is_none());
+    assert!(match_fields("Firefox", &[], "frrfxx", true).is_none());
+
+    // Exact hits win over typo hits
+    let exact = match_fields("Firefox", &[], "firefox", true).unwrap();
+    let typo = match_fields("Firefox", &[], "fierfox", true).unwrap();
+    assert!(exact.quality > typo.quality);
+}
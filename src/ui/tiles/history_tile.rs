@@ -0,0 +1,74 @@
+use gio::glib::object::ObjectExt;
+use gtk4::prelude::WidgetExt;
+
+use super::util::{SherlockSearch, TileBuilder};
+use super::Tile;
+use crate::actions::{execute_from_attrs, get_attrs_map};
+use crate::g_subclasses::sherlock_row::SherlockRow;
+use crate::launcher::history_launcher::HistoryLauncher;
+use crate::launcher::{ranking::Score, Launcher, ResultItem};
+use crate::loader::history_loader::HistoryReader;
+
+impl Tile {
+    pub fn history_tile(
+        launcher: &Launcher,
+        keyword: &str,
+        history: &HistoryLauncher,
+    ) -> Vec<ResultItem> {
+        let entries = HistoryReader::new()
+            .and_then(|reader| reader.read())
+            .unwrap_or_default();
+        let len = entries.len().max(1) as f32;
+        let mut results: Vec<ResultItem> = Vec::with_capacity(entries.len());
+
+        for (index, entry) in entries.iter().enumerate() {
+            if let Some(matched) = entry.query.fuzzy_match(keyword) {
+                let builder = TileBuilder::new("/dev/skxxtz/sherlock/ui/tile.ui");
+                builder.object.set_spawn_focus(launcher.spawn_focus);
+                builder.object.set_shortcut(launcher.shortcut);
+
+                if launcher.name.is_empty() {
+                    builder.category.set_visible(false);
+                }
+                builder.category.set_text(&launcher.name);
+                builder.title.set_markup(&matched.highlight(&entry.query));
+                builder.icon.set_icon_name(Some(&history.icon));
+                if entry.mode != "all" {
+                    builder.display_tag_start(&Some(entry.mode.clone()), keyword);
+                }
+
+                // Construct attrs and enable action capabilities
+                let attrs = get_attrs_map(vec![
+                    ("method", &launcher.method),
+                    ("keyword", keyword),
+                    ("query", &entry.query),
+                    ("mode", &entry.mode),
+                ]);
+                builder
+                    .object
+                    .connect("row-should-activate", false, move |row| {
+                        let row = row.first().map(|f| f.get::<SherlockRow>().ok())??;
+                        execute_from_attrs(&row, &attrs);
+                        None
+                    });
+
+                let shortcut_holder = match launcher.shortcut {
+                    true => builder.shortcut_holder,
+                    _ => None,
+                };
+                // Newer queries first, better matches pull entries up
+                let score = Score {
+                    priority: launcher.priority as f32 + index as f32 / len,
+                    quality: matched.quality(),
+                    ..Default::default()
+                };
+                results.push(ResultItem {
+                    priority: score.apply(&builder.object),
+                    row_item: builder.object,
+                    shortcut_holder,
+                });
+            }
+        }
+        results
+    }
+}
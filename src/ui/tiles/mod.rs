@@ -1,9 +1,12 @@
+pub mod app_category_tile;
 pub mod app_tile;
 pub mod bulk_text_tile;
 pub mod calc_tile;
 pub mod clipboard_tile;
 pub mod error_tile;
 pub mod event_tile;
+pub mod fuzzy;
+pub mod history_tile;
 pub mod mpris_tile;
 pub mod pipe_tile;
 pub mod process_tile;
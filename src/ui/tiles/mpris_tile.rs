@@ -7,7 +7,7 @@ use gtk4::{gdk, Image, Label, Overlay};
 use super::util::{AsyncOptions, TileBuilder};
 use super::Tile;
 use crate::launcher::audio_launcher::MusicPlayerLauncher;
-use crate::launcher::{Launcher, ResultItem};
+use crate::launcher::{ranking::Score, Launcher, ResultItem};
 
 impl Tile {
     pub fn mpris_tile(
@@ -74,7 +74,7 @@ impl Tile {
             _ => None,
         };
         let res = ResultItem {
-            priority: launcher.priority as f32,
+            priority: Score::new(launcher.priority as f32).apply(&builder.object),
             row_item: builder.object,
             shortcut_holder,
         };
@@ -16,15 +16,17 @@ use super::Tile;
 
 impl Tile {
     pub fn pipe_data(lines: &Vec<PipeData>, method: &str, keyword: &str) -> Vec<SherlockRow> {
-        let mut results: Vec<SherlockRow> = Default::default();
+        let mut results: Vec<(i32, SherlockRow)> = Default::default();
 
         for item in lines {
-            if item.fuzzy_match(keyword) || item.binary.is_some() {
+            let matched = item.fuzzy_match(keyword);
+            if matched.is_some() || item.binary.is_some() {
                 let builder = TileBuilder::new("/dev/skxxtz/sherlock/ui/tile.ui");
                 builder.object.set_spawn_focus(true);
 
                 if let Some(title) = &item.title {
-                    builder.title.set_text(&title);
+                    let title_match = matched.clone().unwrap_or_default();
+                    builder.title.set_markup(&title_match.highlight(title));
                 }
                 if let Some(desc) = &item.description {
                     builder.category.set_text(&desc);
@@ -72,9 +74,12 @@ impl Tile {
                         execute_from_attrs(&row, &attrs);
                         None
                     });
-                results.push(builder.object);
+                let score = matched.map_or(0, |m| m.score);
+                results.push((score, builder.object));
             }
         }
-        return results;
+        // Stable sort to keep the piped order for equally good matches
+        results.sort_by_key(|(score, _)| std::cmp::Reverse(*score));
+        return results.into_iter().map(|(_, row)| row).collect();
     }
 }
@@ -3,9 +3,9 @@ use gtk4::prelude::*;
 use crate::actions::{execute_from_attrs, get_attrs_map};
 use crate::g_subclasses::sherlock_row::SherlockRow;
 use crate::launcher::process_launcher::ProcessLauncher;
-use crate::launcher::{Launcher, ResultItem};
+use crate::launcher::{ranking::Score, Launcher, ResultItem};
 
-use super::util::TileBuilder;
+use super::util::{SherlockSearch, TileBuilder};
 use super::Tile;
 
 impl Tile {
@@ -17,7 +17,7 @@ impl Tile {
         let mut results: Vec<ResultItem> = Default::default();
 
         for (key, value) in proc.processes.iter() {
-            if value.to_lowercase().contains(&keyword.to_lowercase()) {
+            if let Some(matched) = value.fuzzy_match(keyword) {
                 let builder = TileBuilder::new("/dev/skxxtz/sherlock/ui/tile.ui");
                 builder.object.set_spawn_focus(launcher.spawn_focus);
                 builder.object.set_shortcut(launcher.shortcut);
@@ -26,7 +26,7 @@ impl Tile {
                     builder.category.set_visible(false);
                 }
                 builder.category.set_text(&launcher.name);
-                builder.title.set_markup(&value);
+                builder.title.set_markup(&matched.highlight(value));
                 builder.icon.set_icon_name(Some(&proc.icon));
                 let ppid = key.0;
                 let cpid = key.1;
@@ -55,7 +55,12 @@ impl Tile {
                     _ => None,
                 };
                 results.push(ResultItem {
-                    priority: launcher.priority as f32,
+                    priority: Score {
+                        priority: launcher.priority as f32,
+                        quality: matched.quality(),
+                        ..Default::default()
+                    }
+                    .apply(&builder.object),
                     row_item: builder.object,
                     shortcut_holder,
                 });
@@ -1,11 +1,19 @@
 use crate::{
     g_subclasses::sherlock_row::SherlockRow,
     launcher::{Launcher, ResultItem},
-    loader::pipe_loader::PipeData,
+    loader::{pipe_loader::PipeData, util::AppData},
     CONFIG,
 };
 use gtk4::{prelude::*, Box, Builder, Image, Label, Overlay, TextView};
-use std::collections::{HashMap, HashSet};
+use std::collections::HashMap;
+
+use super::fuzzy::{fuzzy_score, match_fields, FieldMatch, FuzzyMatch, SearchField};
+
+// Weights of secondary fields relative to a hit in the name
+const UNTRANSLATED_NAME_WEIGHT: f32 = 0.9;
+const KEYWORD_WEIGHT: f32 = 0.8;
+const GENERIC_NAME_WEIGHT: f32 = 0.7;
+const COMMENT_WEIGHT: f32 = 0.5;
 
 #[derive(Debug)]
 pub struct AsyncLauncherTile {
@@ -168,22 +176,57 @@ impl TileBuilder {
 }
 
 pub trait SherlockSearch {
-    fn fuzzy_match<T: AsRef<str>>(&self, substring: T) -> bool;
+    fn fuzzy_match<T: AsRef<str>>(&self, pattern: T) -> Option<FuzzyMatch>;
 }
 
 impl SherlockSearch for String {
-    fn fuzzy_match<T>(&self, substring: T) -> bool
+    fn fuzzy_match<T>(&self, pattern: T) -> Option<FuzzyMatch>
     where
-        Self: AsRef<str>,
         T: AsRef<str>,
     {
-        let char_pattern: HashSet<char> = substring.as_ref().chars().collect();
-        let concat_str: String = self.chars().filter(|s| char_pattern.contains(s)).collect();
-        concat_str.contains(substring.as_ref())
+        fuzzy_score(self, pattern.as_ref())
+    }
+}
+impl AppData {
+    /// Matches the query against the name, untranslated name, keywords, generic
+    /// name and comment
+    pub fn search(&self, name: &str, query: &str, typo_tolerant: bool) -> Option<FieldMatch> {
+        let mut fields: Vec<SearchField> = self
+            .keywords
+            .split(';')
+            .filter(|k| !k.is_empty())
+            .map(|text| SearchField {
+                text,
+                weight: KEYWORD_WEIGHT,
+                contiguous: false,
+            })
+            .collect();
+        if let Some(text) = self.untranslated_name.as_deref() {
+            fields.push(SearchField {
+                text,
+                weight: UNTRANSLATED_NAME_WEIGHT,
+                contiguous: false,
+            });
+        }
+        if let Some(text) = self.generic_name.as_deref() {
+            fields.push(SearchField {
+                text,
+                weight: GENERIC_NAME_WEIGHT,
+                contiguous: false,
+            });
+        }
+        if let Some(text) = self.comment.as_deref() {
+            fields.push(SearchField {
+                text,
+                weight: COMMENT_WEIGHT,
+                contiguous: true,
+            });
+        }
+        match_fields(name, &fields, query, typo_tolerant)
     }
 }
 impl SherlockSearch for PipeData {
-    fn fuzzy_match<T>(&self, substring: T) -> bool
+    fn fuzzy_match<T>(&self, pattern: T) -> Option<FuzzyMatch>
     where
         T: AsRef<str>,
     {
@@ -192,14 +235,8 @@ impl SherlockSearch for PipeData {
             Some(_) => &self.title,
             None => &self.description,
         };
-        if let Some(search_in) = search_in {
-            let char_pattern: HashSet<char> = substring.as_ref().chars().collect();
-            let concat_str: String = search_in
-                .chars()
-                .filter(|s| char_pattern.contains(s))
-                .collect();
-            return concat_str.contains(substring.as_ref());
-        }
-        return false;
+        search_in
+            .as_ref()
+            .and_then(|search_in| fuzzy_score(search_in, pattern.as_ref()))
     }
 }
@@ -19,6 +19,16 @@ pub fn show_stack_page<T: AsRef<str>>(page_name: T, transition: Option<StackTran
         }
     });
 }
+pub fn set_search_text(text: &str) {
+    APP_STATE.with(|state| {
+        if let Some(ref state) = *state.borrow() {
+            if let Some(search_bar) = &state.search_bar {
+                search_bar.set_text(text);
+                search_bar.set_position(-1);
+            }
+        }
+    });
+}
 pub fn remove_stack_children() {
     APP_STATE.with(|state| {
         if let Some(ref state) = *state.borrow() {
@@ -146,6 +156,10 @@ pub struct ConfKeys {
     pub next_mod: Option<ModifierType>,
     pub prev: Option<Key>,
     pub prev_mod: Option<ModifierType>,
+    pub history_next: Option<Key>,
+    pub history_next_mod: Option<ModifierType>,
+    pub history_prev: Option<Key>,
+    pub history_prev_mod: Option<ModifierType>,
     pub shortcut_modifier: Option<ModifierType>,
     pub shortcut_modifier_str: String,
 }
@@ -160,6 +174,12 @@ impl ConfKeys {
                 Some(next) => ConfKeys::eval_bind_combination(next),
                 _ => (None, None),
             };
+            let (history_prev_mod, history_prev) = ConfKeys::eval_bind_combination(
+                c.binds.history_prev.as_deref().unwrap_or("alt-up"),
+            );
+            let (history_next_mod, history_next) = ConfKeys::eval_bind_combination(
+                c.binds.history_next.as_deref().unwrap_or("alt-down"),
+            );
             let shortcut_modifier = match &c.binds.modifier {
                 Some(shortcut) => ConfKeys::eval_mod(shortcut),
                 _ => Some(ModifierType::CONTROL_MASK),
@@ -170,6 +190,10 @@ impl ConfKeys {
                 next_mod,
                 prev,
                 prev_mod,
+                history_next,
+                history_next_mod,
+                history_prev,
+                history_prev_mod,
                 shortcut_modifier,
                 shortcut_modifier_str,
             };
@@ -182,6 +206,10 @@ impl ConfKeys {
             next_mod: None,
             prev: None,
             prev_mod: None,
+            history_next: None,
+            history_next_mod: None,
+            history_prev: None,
+            history_prev_mod: None,
             shortcut_modifier: None,
             shortcut_modifier_str: String::new(),
         }